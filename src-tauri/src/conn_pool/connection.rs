@@ -1,61 +1,246 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::io::Read;
+use std::fs::File;
+use std::io::{copy, ErrorKind, Read, Write};
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Condvar, Mutex};
 use std::time::Duration;
 
-use libssh_rs::{AuthStatus, Session, SshKey, SshOption};
+use libssh_rs::{AuthStatus, FileType, OpenFlags, PublicKeyHashType, Session, SshKey, SshOption};
 use regex::Regex;
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
-use crate::conn_pool::{DeviceConnection, DeviceConnectionUserInfo, Id};
+use crate::conn_pool::{
+    CancelToken, Capabilities, ConnectionMetrics, DeviceConnection, DeviceConnectionUserInfo,
+    DeviceInfo, DirChange, DiskUsage, ExecOutput, Id, Sig,
+};
 use crate::device_manager::Device;
 use crate::error::Error;
+use crate::remote_files::{FileItem, FileStat, PermInfo};
 
 impl DeviceConnection {
     pub(crate) fn new(device: Device, ssh_dir: Option<&Path>) -> Result<DeviceConnection, Error> {
         let session = Session::new()?;
         Self::session_init(&session)?;
 
-        session.set_option(SshOption::Hostname(device.host.clone()))?;
-        session.set_option(SshOption::Port(device.port.clone()))?;
+        let (hostname, port_override) = Device::parse_host(&device.host)?;
+        let target_port = port_override.unwrap_or(device.port);
+
+        // With a jump host configured, the real target is only reachable through it: tunnel a
+        // loopback port to it over the bastion, and connect `session` to that instead of
+        // `hostname` directly. Everything past this point (host key check, auth, ...) still
+        // talks to the actual target — it just does so through the tunnel.
+        let jump_tunnel = match &device.jump_host {
+            Some(jump) => Some(crate::conn_pool::forward::JumpTunnel::open(
+                jump,
+                ssh_dir,
+                &hostname,
+                target_port,
+            )?),
+            None => None,
+        };
+        let (connect_host, connect_port) = match &jump_tunnel {
+            Some(tunnel) => (String::from("127.0.0.1"), tunnel.local_port),
+            None => (hostname, target_port),
+        };
+
+        session.set_option(SshOption::Hostname(connect_host))?;
+        session.set_option(SshOption::Port(connect_port))?;
         session.set_option(SshOption::User(Some(device.username.clone())))?;
+        // Bounds the TCP connect + SSH handshake, not individual commands afterwards — an
+        // off/unreachable TV would otherwise hang for the OS's full TCP connect timeout.
+        session.set_option(SshOption::Timeout(device.connect_timeout()))?;
+        // Nagle's algorithm batches up small writes, which is exactly wrong for interactive
+        // PTY/shell keystrokes — each keystroke otherwise waits on the previous packet's ACK.
+        // libssh doesn't expose raw send/recv buffer sizing the way a bare socket would, so
+        // that part of socket tuning isn't available through this API.
+        if let Err(e) = session.set_option(SshOption::Nodelay(true)) {
+            log::warn!("Failed to set TCP_NODELAY: {e:?}");
+        }
+        // libssh negotiates against whatever the server actually offers, so requesting
+        // "zlib" still connects fine to a device that only supports "none" — this just
+        // expresses a preference, it's not a hard requirement.
+        session.set_option(SshOption::Compression(String::from(
+            if device.compression.unwrap_or(false) {
+                "zlib"
+            } else {
+                "none"
+            },
+        )))?;
 
-        session.connect()?;
+        // "Connection refused"/timeout almost always means the TV's Developer Mode app
+        // has expired rather than a real network problem, so point users at the fix
+        // instead of a raw socket error.
+        session.connect().map_err(|e| match Error::from(e) {
+            Error::IO {
+                code: ErrorKind::ConnectionRefused,
+                ..
+            }
+            | Error::Timeout if jump_tunnel.is_none() => Error::DevModeLikelyOff,
+            e if jump_tunnel.is_some() => Error::JumpHostFailed {
+                hop: String::from("target"),
+                message: e.to_string(),
+            },
+            e => e,
+        })?;
 
-        if let Some(private_key) = &device.private_key {
-            let passphrase = device.valid_passphrase();
-            let priv_key_content = private_key.content(ssh_dir)?;
-            let priv_key = SshKey::from_privkey_base64(&priv_key_content, passphrase.as_deref())?;
+        // A server that sent no banner at all makes this `Err`, same as a real transport
+        // failure would — either way there's nothing to show, so just treat it as absent.
+        let banner = session
+            .get_server_banner()
+            .ok()
+            .filter(|b| !b.is_empty());
 
-            if session.userauth_publickey(None, &priv_key)? != AuthStatus::Success {
-                return Err(Error::Authorization {
-                    message: "Key authorization failed".to_string(),
-                });
+        // Trust-on-first-use: a device with no fingerprint on file hasn't been trusted yet,
+        // so fail closed and hand the UI the fingerprint to prompt the user with rather than
+        // silently proceeding — the whole point of pinning is defeated if the very first
+        // connection (the one a MITM would target) is let through unchecked.
+        match &device.host_key_fingerprint {
+            Some(expected) => {
+                let actual = Self::host_key_fingerprint(&session)?;
+                if &actual != expected {
+                    return Err(Error::HostKeyChanged {
+                        fingerprint: actual,
+                    });
+                }
             }
-        } else if let Some(password) = &device.password {
-            if session.userauth_password(None, Some(password))? != AuthStatus::Success {
-                return Err(Error::Authorization {
-                    message: "Bad SSH password".to_string(),
-                });
+            None => {
+                let fingerprint = Self::host_key_fingerprint(&session)?;
+                return Err(Error::UnknownHostKey { fingerprint });
+            }
+        }
+
+        // Cheap and RFC-standard: try `none` auth before anything configured. Some webOS
+        // devices in a fully-open dev configuration accept it outright, skipping the rest of
+        // this entirely; a server that actually requires auth just denies it like any other
+        // rejected method, so this can't interfere with devices needing real credentials.
+        if session.userauth_none(None)? != AuthStatus::Success {
+            if let Some(private_key) = &device.private_key {
+                let passphrase = device.valid_passphrase();
+                let priv_key_content = private_key.content(ssh_dir)?;
+                let priv_key =
+                    SshKey::from_privkey_base64(&priv_key_content, passphrase.as_deref())
+                        .map_err(|_| {
+                            if passphrase.is_none() {
+                                Error::PassphraseRequired
+                            } else {
+                                Error::BadPassphrase
+                            }
+                        })?;
+
+                if session.userauth_publickey(None, &priv_key)? != AuthStatus::Success {
+                    return Err(Error::AuthFailed {
+                        methods_tried: vec![String::from("publickey")],
+                    });
+                }
+            } else if device.use_agent.unwrap_or(false) {
+                // `ssh-agent` keeps its own identities; try each via libssh's agent-backed
+                // auto auth, falling back to password auth when the agent has none that work.
+                let agent_ok = matches!(
+                    session.userauth_publickey_auto(None, None),
+                    Ok(AuthStatus::Success)
+                );
+                if !agent_ok {
+                    if let Some(password) = &device.password {
+                        if session.userauth_password(None, Some(password))? != AuthStatus::Success
+                        {
+                            return Err(Error::AuthFailed {
+                                methods_tried: vec![
+                                    String::from("publickey(agent)"),
+                                    String::from("password"),
+                                ],
+                            });
+                        }
+                    } else {
+                        return Err(Error::AuthFailed {
+                            methods_tried: vec![String::from("publickey(agent)")],
+                        });
+                    }
+                }
+            } else if let Some(password) = &device.password {
+                if session.userauth_password(None, Some(password))? != AuthStatus::Success {
+                    return Err(Error::AuthFailed {
+                        methods_tried: vec![String::from("password")],
+                    });
+                }
+            } else {
+                // Nothing is configured on this device beyond bare `none` auth, which has
+                // already been tried above, so there's nothing left we could have tried —
+                // distinct from a configured method the server actively rejected.
+                return Err(Error::AuthMethodsExhausted);
             }
-        } else if session.userauth_none(None)? != AuthStatus::Success {
-            return Err(Error::Authorization {
-                message: "Host needs authorization".to_string(),
-            });
         }
+        // The authentication banner arrives during userauth, so it can only be read once auth
+        // has been attempted. A server that sent no banner at all makes this `Err`, same as a
+        // real transport failure would — either way there's nothing to show, so just treat it
+        // as absent.
+        let banner = session
+            .get_issue_banner()
+            .ok()
+            .filter(|b| !b.is_empty());
         let connection = DeviceConnection {
             id: Uuid::new_v4(),
             device: device.clone(),
             user: DeviceConnectionUserInfo::new(&session)?,
             session,
             last_ok: Mutex::new(true),
+            channel_gate: Arc::new((
+                Mutex::new(
+                    device
+                        .max_channels
+                        .unwrap_or(crate::conn_pool::DEFAULT_MAX_CHANNELS),
+                ),
+                Condvar::new(),
+            )),
+            capabilities: Mutex::new(None),
+            banner,
+            bytes_read: AtomicU64::new(0),
+            bytes_written: AtomicU64::new(0),
+            commands_executed: AtomicU64::new(0),
+            _jump_tunnel: jump_tunnel,
         };
         log::info!("{:?} created", connection);
         return Ok(connection);
     }
 
+    /// Stable identifier for this connection, distinct from any per-channel id logged by an
+    /// individual `exec*` call — useful for correlating every command run over the same
+    /// underlying SSH session in a support bundle's logs.
+    pub fn id(&self) -> Uuid {
+        return self.id;
+    }
+
+    /// The SSH authentication banner captured during connect, if the server sent one. webOS
+    /// devices in developer mode often put the dev-mode session's expiry here.
+    pub fn banner(&self) -> Option<&str> {
+        return self.banner.as_deref();
+    }
+
+    /// Cumulative byte/command counters since this connection was established, for diagnosing
+    /// whether a slow transfer is actually progressing. Only `exec` and spawned `Proc`s update
+    /// them; SFTP file transfers and the other `exec_*` methods leave them unchanged.
+    pub fn metrics(&self) -> ConnectionMetrics {
+        return ConnectionMetrics {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            commands_executed: self.commands_executed.load(Ordering::Relaxed),
+        };
+    }
+
+    pub(crate) fn record_read(&self, n: usize) {
+        self.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_written(&self, n: usize) {
+        self.bytes_written.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_command(&self) {
+        self.commands_executed.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(super) fn reset_last_ok(&self) {
         *self
             .last_ok
@@ -70,6 +255,1269 @@ impl DeviceConnection {
             .expect("Failed to lock DeviceConnection::last_ok") = true;
     }
 
+    /// Runs `command` to completion and returns its stdout, stderr and exit status.
+    ///
+    /// Unlike the plugin-level `exec` command, this never treats a non-zero exit
+    /// status as an error, so callers can decide for themselves whether to
+    /// surface `ExecOutput::stderr` (e.g. deprecation notices printed by tools
+    /// that still exit 0).
+    ///
+    /// Note: `libssh_rs::Channel` only exposes the standard stdout/stderr (`ext == 1`)
+    /// streams, so there's no hook here to capture other SSH extended-data channel ids
+    /// separately; any such data a remote service emits is not observable through this API.
+    ///
+    /// The EOF/exit-status ordering above (drain both streams to EOF, *then* read the exit
+    /// status) is covered by this module's tests, which run `exec` against a scripted
+    /// in-process `russh` server (`tests::mock_server`) instead of a real device.
+    pub fn exec(&self, command: &str, stdin: Option<&[u8]>) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec {:?}", self.id, redact_secrets(command));
+        self.record_command();
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            self.record_written(stdin.len());
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        ch.stdout().read_to_end(&mut stdout)?;
+        let mut stderr = Vec::<u8>::new();
+        ch.stderr().read_to_end(&mut stderr)?;
+        self.record_read(stdout.len() + stderr.len());
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Runs `commands` in order over this connection, stopping at the first command that
+    /// exits non-zero and returning `Error::ExitStatus` with `index` set to which one failed.
+    /// Reuses [`DeviceConnection::exec`] per command rather than chaining them into one shell
+    /// invocation, so each command's stdout is reported separately and a later command never
+    /// accidentally runs if an earlier one's exit status was ignored (as `&&` would allow if a
+    /// command happened to exit 0 despite failing).
+    pub fn exec_batch(&self, commands: &[String]) -> Result<Vec<Vec<u8>>, Error> {
+        let mut outputs = Vec::with_capacity(commands.len());
+        for (index, command) in commands.iter().enumerate() {
+            let output = self.exec(command, None)?;
+            if output.status != 0 {
+                return Err(
+                    Error::exit_status(command.clone(), output.status, output.stderr, Some(self.id.to_string()))
+                        .with_index(index),
+                );
+            }
+            outputs.push(output.stdout);
+        }
+        return Ok(outputs);
+    }
+
+    /// Like [`DeviceConnection::exec`], but for `ares-install`-style webOS command line tools:
+    /// on a non-zero exit, their stderr is scanned for a recognized failure token (e.g.
+    /// `FAILED_REMOVE`, `INVALID_PACKAGE`) and turned into [`Error::WebosTool`] instead of the
+    /// generic [`Error::ExitStatus`], so the frontend can show a specific message rather than a
+    /// raw exit code. Falls back to `ExitStatus` when nothing recognizable is found.
+    pub fn exec_installer(&self, command: &str, stdin: Option<&[u8]>) -> Result<ExecOutput, Error> {
+        let output = self.exec(command, stdin)?;
+        if output.status != 0 {
+            if let Some(err) = classify_installer_error(&output.stderr) {
+                return Err(err);
+            }
+            return Err(Error::exit_status(command, output.status, output.stderr, Some(self.id.to_string())));
+        }
+        return Ok(output);
+    }
+
+    /// Like [`DeviceConnection::exec`], but for commands that embed a secret (e.g. a
+    /// `luna-send` call with a password baked into its payload). The command is never
+    /// logged, not even redacted — only the connection id, so a caller's debug log still
+    /// shows that *a* command ran without risking the secret ending up in a bug report.
+    pub fn exec_secret(&self, command: &str, stdin: Option<&[u8]>) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec <redacted>", self.id);
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        ch.stdout().read_to_end(&mut stdout)?;
+        let mut stderr = Vec::<u8>::new();
+        ch.stderr().read_to_end(&mut stderr)?;
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Like [`DeviceConnection::exec`], but runs `command` as root, probing on every call for
+    /// how: already root (the `root`/port-22 login some devices allow), or passwordless
+    /// `sudo` (some devmode shells have it configured). Returns
+    /// [`Error::ElevationUnavailable`] if neither is available rather than silently running the
+    /// command unprivileged.
+    pub fn exec_elevated(&self, command: &str, stdin: Option<&[u8]>) -> Result<ExecOutput, Error> {
+        let wrapped = match self.elevation_method()? {
+            ElevationMethod::AlreadyRoot => command.to_string(),
+            ElevationMethod::Sudo => {
+                format!("sudo -n sh -c {}", shell_quote(command))
+            }
+        };
+        return self.exec(&wrapped, stdin);
+    }
+
+    /// Determines how (if at all) this connection can run commands as root. Re-probes every
+    /// call rather than caching alongside [`DeviceConnection::capabilities`] — unlike webOS
+    /// version or installed tooling, whether `sudo` is configured isn't expected to be stable
+    /// enough across a connection's lifetime to bank on a stale answer.
+    fn elevation_method(&self) -> Result<ElevationMethod, Error> {
+        let whoami = self.exec("id -u", None)?;
+        if String::from_utf8_lossy(&whoami.stdout).trim() == "0" {
+            return Ok(ElevationMethod::AlreadyRoot);
+        }
+        let probe = self.exec("sudo -n true", None)?;
+        if probe.status == 0 {
+            return Ok(ElevationMethod::Sudo);
+        }
+        return Err(Error::ElevationUnavailable);
+    }
+
+    /// Like [`DeviceConnection::exec`], but runs `command` through `sh -lc` so it inherits the
+    /// login environment (notably `PATH`), for `ares-*` webOS CLI tools that some builds only
+    /// put on the interactive-login `PATH` rather than the bare non-interactive one `exec` uses
+    /// directly. Opt-in rather than the default, since sourcing a login shell changes quoting
+    /// semantics — `command` is itself re-quoted as a single argument to `sh -c`, so any quotes
+    /// or `$` it contains are interpreted by the remote shell an extra time compared to plain
+    /// `exec`.
+    pub fn exec_login_shell(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+    ) -> Result<ExecOutput, Error> {
+        let wrapped = format!("sh -lc {}", shell_quote(command));
+        return self.exec(&wrapped, stdin);
+    }
+
+    /// Like [`DeviceConnection::exec`], but sets `env` on the channel before running `command`.
+    /// Most `sshd` configs reject `set_env`/`request_env` for vars not listed in
+    /// `AcceptEnv`, so a rejected var is silently dropped rather than failing the whole
+    /// command — callers that need it to take effect regardless should prefix it onto
+    /// `command` themselves (e.g. `"LD_LIBRARY_PATH=... cmd"`).
+    pub fn exec_with_env(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        env: &[(String, String)],
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_with_env {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        for (key, value) in env {
+            match ch.request_env(key, value) {
+                Ok(_) => {}
+                Err(libssh_rs::Error::RequestDenied(s)) => {
+                    log::warn!("{self:?} sshd rejected env {key}: {s:?}");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        ch.stdout().read_to_end(&mut stdout)?;
+        let mut stderr = Vec::<u8>::new();
+        ch.stderr().read_to_end(&mut stderr)?;
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Runs `command` and deserializes its trimmed stdout as JSON, for the many webOS
+    /// introspection commands (`luna-send`, `device_info.json`, ...) that return it. On a
+    /// parse failure, the raw stdout is attached to `Error::JsonParse` so callers can see
+    /// what the device actually sent back instead of just "invalid JSON".
+    pub fn exec_json<T: serde::de::DeserializeOwned>(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+    ) -> Result<T, Error> {
+        let output = self.exec(command, stdin)?;
+        let raw = String::from_utf8_lossy(&output.stdout);
+        let trimmed = raw.trim();
+        return serde_json::from_str(trimmed).map_err(|_| Error::JsonParse {
+            raw: String::from(trimmed),
+        });
+    }
+
+    /// Calls webOS's luna bus, JSON-encoding `params` and shell-quoting the result instead of
+    /// leaving every call site to hand-build a `luna-send ... '{...}'` string and get the
+    /// escaping wrong. Maps a reply with `returnValue: false` to [`Error::LunaCallFailed`]
+    /// carrying its `errorText`, the same convention the desktop frontend's own luna helper
+    /// already follows.
+    ///
+    /// Rust-side callers only: being generic over `T`/`R`, this can't itself be a
+    /// `#[tauri::command]` (those require a fixed, monomorphic signature), and the frontend's
+    /// `RemoteLunaService.call` already covers the same ground — plus pub/priv bus selection,
+    /// subscriptions, and webOS-specific error classification this doesn't attempt. A
+    /// non-generic wrapper over one concrete JSON shape could still be added as a command if a
+    /// future Rust-side (not frontend) caller needs this.
+    pub fn luna_send<T: serde::Serialize, R: serde::de::DeserializeOwned>(
+        &self,
+        uri: &str,
+        params: &T,
+    ) -> Result<R, Error> {
+        let payload = serde_json::to_string(params)?;
+        let command = format!("luna-send -n 1 {} {}", shell_quote(uri), shell_quote(&payload));
+        let reply: serde_json::Value = self.exec_json(&command, None)?;
+        if reply.get("returnValue").and_then(|v| v.as_bool()) == Some(false) {
+            return Err(Error::LunaCallFailed {
+                uri: uri.to_string(),
+                error_text: reply
+                    .get("errorText")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+            });
+        }
+        return Ok(serde_json::from_value(reply)?);
+    }
+
+    /// Like [`DeviceConnection::exec`], but decodes stdout as text instead of returning raw
+    /// bytes, for devices/locales whose tools don't emit UTF-8 (legacy webOS builds running in
+    /// CJK locales are the common case). `encoding` is a [WHATWG label](https://encoding.spec.whatwg.org/#names-and-labels)
+    /// such as `"gbk"` or `"shift_jis"`; an unrecognized or absent label falls back to UTF-8,
+    /// replacing invalid sequences rather than failing outright since this is generally used for
+    /// human-readable log output where a best-effort decode beats an error. Unlike `exec`, a
+    /// non-zero exit status is treated as an error, matching the plugin-level `exec` command.
+    pub fn exec_text(&self, command: &str, encoding: Option<&str>) -> Result<String, Error> {
+        let output = self.exec(command, None)?;
+        if output.status != 0 {
+            return Err(Error::exit_status(command, output.status, output.stderr, Some(self.id.to_string())));
+        }
+        let encoding = encoding
+            .and_then(encoding_rs::Encoding::for_label)
+            .unwrap_or(encoding_rs::UTF_8);
+        let (text, _, _) = encoding.decode(&output.stdout);
+        return Ok(text.into_owned());
+    }
+
+    /// Blocks until a channel permit is free, then holds it until the returned guard is
+    /// dropped. Caps how many channels this connection has open at once. `pub(crate)` (rather
+    /// than private) so long-lived channel owners outside this module — `Proc`'s `spawn`/`tail`
+    /// channels — go through the same cap as every `exec`-family method here, instead of only
+    /// half-enforcing it.
+    pub(crate) fn acquire_channel_permit(&self) -> ChannelPermit {
+        let (lock, cvar) = &*self.channel_gate;
+        let mut available = lock.lock().expect("Failed to lock DeviceConnection::channel_gate");
+        while *available == 0 {
+            available = cvar.wait(available).expect("Failed to wait on channel_gate");
+        }
+        *available -= 1;
+        return ChannelPermit {
+            gate: self.channel_gate.clone(),
+        };
+    }
+
+    /// Returns the channel's exit status, or `Error::ExitSignal` if the remote process was
+    /// killed by a signal instead of exiting normally (e.g. OOM-killed mid-command).
+    fn exit_status(ch: &libssh_rs::Channel) -> Result<i32, Error> {
+        if let Some(status) = ch.get_exit_status() {
+            return Ok(status);
+        }
+        if let Some(signal) = ch.get_exit_signal() {
+            return Err(Error::ExitSignal {
+                signal: signal.signal_name.unwrap_or_else(|| String::from("UNKNOWN")),
+                core_dumped: signal.core_dumped,
+            });
+        }
+        return Ok(0);
+    }
+
+    /// Like [`DeviceConnection::exec`], but aborts with `Error::OutputTooLarge` once the
+    /// combined stdout+stderr exceeds `max_output`, closing the channel instead of letting a
+    /// runaway command (e.g. `cat /dev/urandom`) OOM the desktop app.
+    pub fn exec_limited(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        max_output: usize,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_limited {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let mut buf = [0u8; 8192];
+        while !ch.is_closed() && !ch.is_eof() {
+            let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stdout.extend_from_slice(&buf[..size]);
+            }
+            let size = ch.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&buf[..size]);
+            }
+            if stdout.len() + stderr.len() > max_output {
+                ch.close()?;
+                return Err(Error::OutputTooLarge { limit: max_output });
+            }
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Returns usage for every mount `path` spans, via [`parse_df_output`].
+    pub fn disk_usage(&self, path: &str) -> Result<Vec<DiskUsage>, Error> {
+        let command = format!("df -k -- {}", shell_quote(path));
+        let output = self.exec(&command, None)?;
+        if output.status != 0 {
+            return Err(Error::exit_status(command, output.status, output.stderr, Some(self.id.to_string())));
+        }
+        return Ok(parse_df_output(&String::from_utf8_lossy(&output.stdout)));
+    }
+
+    /// Returns the last `lines` lines of the remote file at `path`, via `tail -n`, which reads
+    /// only from the end of the file rather than streaming the whole thing over like
+    /// [`Self::download`] would — the efficient way to grab a log excerpt. Bounded by
+    /// [`Self::exec_limited`] in case `path` turns out to have a handful of enormous lines.
+    pub fn read_tail(&self, path: &str, lines: usize) -> Result<Vec<String>, Error> {
+        let command = format!("tail -n {} -- {}", lines, shell_quote(path));
+        let output = self.exec_limited(&command, None, MAX_TAIL_BYTES)?;
+        if output.status != 0 {
+            return Err(Error::exit_status(command, output.status, output.stderr, Some(self.id.to_string())));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        return Ok(text.lines().map(String::from).collect());
+    }
+
+    /// Uploads the local file at `local` to `remote` over SFTP, creating or truncating the
+    /// remote file as needed, and returns the number of bytes copied. When `rate_limit` is
+    /// set, the transfer is throttled to roughly that many bytes/sec so a large push doesn't
+    /// saturate the link and starve the rest of the app.
+    pub fn upload(&self, local: &Path, remote: &str, rate_limit: Option<u64>) -> Result<u64, Error> {
+        let sftp = self.session.sftp()?;
+        let mut rfile = sftp.open(
+            remote,
+            OpenFlags::WRITE_ONLY | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            0o644,
+        )?;
+        let mut lfile = File::open(local)?;
+        return copy_throttled(&mut lfile, &mut rfile, rate_limit);
+    }
+
+    /// Like [`DeviceConnection::upload`], but verifies the transfer afterward: computes a
+    /// sha256 of the local file before the upload, runs `sha256sum` on the device after it, and
+    /// returns [`Error::ChecksumMismatch`] if they disagree. Returns [`Error::Unsupported`] if
+    /// the device has no `sha256sum` to run — there's no MD5 fallback here the way some
+    /// installer tooling has, since comparing an MD5 on one side against a sha256 on the other
+    /// would prove nothing.
+    pub fn upload_verified(
+        &self,
+        local: &Path,
+        remote: &str,
+        rate_limit: Option<u64>,
+    ) -> Result<u64, Error> {
+        let local_checksum = sha256::digest(std::fs::read(local)?.as_slice());
+        let written = self.upload(local, remote, rate_limit)?;
+        let output = self.exec(&format!("sha256sum -- {}", shell_quote(remote)), None)?;
+        if output.status != 0 {
+            return Err(Error::Unsupported);
+        }
+        let remote_checksum = String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_string();
+        if remote_checksum != local_checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: local_checksum,
+                actual: remote_checksum,
+            });
+        }
+        return Ok(written);
+    }
+
+    /// Downloads `remote` over SFTP into the local file at `local`, creating or truncating it
+    /// as needed, and returns the number of bytes copied. See [`DeviceConnection::upload`] for
+    /// `rate_limit`.
+    pub fn download(
+        &self,
+        remote: &str,
+        local: &Path,
+        rate_limit: Option<u64>,
+    ) -> Result<u64, Error> {
+        let sftp = self.session.sftp()?;
+        let mut rfile = sftp.open(remote, OpenFlags::READ_ONLY, 0)?;
+        let mut lfile = File::create(local)?;
+        return copy_throttled(&mut rfile, &mut lfile, rate_limit);
+    }
+
+    /// Recursively uploads every file under `local` to `remote` over SFTP, creating remote
+    /// directories as needed. `on_progress(bytes, files)` is called with running totals after
+    /// each file finishes, so callers can show aggregate progress without polling. Symlinks are
+    /// skipped unless `follow_symlinks` is set, in which case they're followed and uploaded as
+    /// regular files. Continues past per-file failures the way
+    /// [`DeviceConnection::remove_dir`] does, collecting every failed path into
+    /// `Error::PartialFailure` rather than aborting the whole upload over one bad file — by the
+    /// time that's returned, `on_progress`'s last call already reported how many succeeded.
+    pub fn put_dir<F: FnMut(u64, u64)>(
+        &self,
+        local: &Path,
+        remote: &str,
+        follow_symlinks: bool,
+        mut on_progress: F,
+    ) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        if sftp.stat(remote).is_err() {
+            sftp.mkdir(remote, 0o755)?;
+        }
+        let mut bytes = 0u64;
+        let mut files = 0u64;
+        let mut failed = Vec::<String>::new();
+        self.put_dir_walk(
+            local,
+            remote,
+            follow_symlinks,
+            &mut bytes,
+            &mut files,
+            &mut on_progress,
+            &mut failed,
+        )?;
+        if !failed.is_empty() {
+            return Err(Error::PartialFailure { paths: failed });
+        }
+        return Ok(());
+    }
+
+    fn put_dir_walk(
+        &self,
+        local: &Path,
+        remote: &str,
+        follow_symlinks: bool,
+        bytes: &mut u64,
+        files: &mut u64,
+        on_progress: &mut dyn FnMut(u64, u64),
+        failed: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        for entry in std::fs::read_dir(local)? {
+            let entry = entry?;
+            let child_local = entry.path();
+            let Some(name) = child_local.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let child_remote = format!("{}/{}", remote.trim_end_matches('/'), name);
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() && !follow_symlinks {
+                continue;
+            }
+            if file_type.is_dir() || (file_type.is_symlink() && child_local.is_dir()) {
+                if sftp.stat(&child_remote).is_err() {
+                    sftp.mkdir(&child_remote, 0o755)?;
+                }
+                self.put_dir_walk(
+                    &child_local,
+                    &child_remote,
+                    follow_symlinks,
+                    bytes,
+                    files,
+                    on_progress,
+                    failed,
+                )?;
+                continue;
+            }
+            match self.upload(&child_local, &child_remote, None) {
+                Ok(n) => {
+                    *bytes += n;
+                    *files += 1;
+                    on_progress(*bytes, *files);
+                }
+                Err(_) => failed.push(child_remote),
+            }
+        }
+        return Ok(());
+    }
+
+    /// Recursively downloads everything under `remote` over SFTP into `local`, recreating the
+    /// directory tree and preserving file modes where the local filesystem supports it (Unix
+    /// only — a no-op on Windows). `on_progress(bytes, files)` is called with running totals
+    /// after each file finishes. Unlike [`DeviceConnection::put_dir`], a per-file failure here
+    /// is expected often enough (permission-denied on some app's private files is routine) that
+    /// it's folded into the success path: failed paths come back as a warnings list alongside
+    /// the completed transfer rather than `Error::PartialFailure`.
+    pub fn get_dir<F: FnMut(u64, u64)>(
+        &self,
+        remote: &str,
+        local: &Path,
+        mut on_progress: F,
+    ) -> Result<Vec<String>, Error> {
+        std::fs::create_dir_all(local)?;
+        let mut bytes = 0u64;
+        let mut files = 0u64;
+        let mut warnings = Vec::<String>::new();
+        self.get_dir_walk(remote, local, &mut bytes, &mut files, &mut on_progress, &mut warnings)?;
+        return Ok(warnings);
+    }
+
+    fn get_dir_walk(
+        &self,
+        remote: &str,
+        local: &Path,
+        bytes: &mut u64,
+        files: &mut u64,
+        on_progress: &mut dyn FnMut(u64, u64),
+        warnings: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        for entry in sftp.read_dir(remote)? {
+            let Some(name) = entry.name() else {
+                continue;
+            };
+            if name == "." || name == ".." {
+                continue;
+            }
+            let child_remote = format!("{}/{}", remote.trim_end_matches('/'), name);
+            let child_local = local.join(name);
+            if entry.file_type() == Some(FileType::Directory) {
+                if let Err(e) = std::fs::create_dir_all(&child_local) {
+                    warnings.push(format!("{child_remote}: {e}"));
+                    continue;
+                }
+                self.get_dir_walk(&child_remote, &child_local, bytes, files, on_progress, warnings)?;
+                continue;
+            }
+            match self.download(&child_remote, &child_local, None) {
+                Ok(n) => {
+                    *bytes += n;
+                    *files += 1;
+                    on_progress(*bytes, *files);
+                    Self::preserve_mode(&entry, &child_local);
+                }
+                Err(e) => warnings.push(format!("{child_remote}: {e}")),
+            }
+        }
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    fn preserve_mode(entry: &libssh_rs::Metadata, local: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+        if let Some(mode) = entry.permissions() {
+            let _ = std::fs::set_permissions(local, std::fs::Permissions::from_mode(mode));
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn preserve_mode(_entry: &libssh_rs::Metadata, _local: &Path) {}
+
+    /// Reads the whole contents of `path` over SFTP into memory, refusing with
+    /// `Error::OutputTooLarge` past `max_len` rather than buffering an arbitrarily large file.
+    /// The size check happens via `stat` first, so a huge file is rejected before any of it is
+    /// actually transferred. A zero-byte file reads back as an empty `Vec` without error.
+    pub fn read_file(&self, path: &str, max_len: usize) -> Result<Vec<u8>, Error> {
+        let sftp = self.session.sftp()?;
+        let size = sftp.stat(path)?.len().unwrap_or(0) as usize;
+        if size > max_len {
+            return Err(Error::OutputTooLarge { limit: max_len });
+        }
+        let mut file = sftp.open(path, OpenFlags::READ_ONLY, 0)?;
+        let mut buf = Vec::with_capacity(size);
+        file.read_to_end(&mut buf)?;
+        return Ok(buf);
+    }
+
+    /// Writes `data` to `path` over SFTP, creating or truncating it and setting `mode`.
+    /// Writes to a temp name in the same directory first and renames it into place, so a
+    /// reader never observes a partially-written file. When `create_parents` is set, missing
+    /// parent directories are created first; otherwise a missing parent surfaces as
+    /// `Error::NotFound` rather than the more opaque SFTP failure it'd otherwise be.
+    pub fn write_file(
+        &self,
+        path: &str,
+        data: &[u8],
+        mode: u32,
+        create_parents: bool,
+    ) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        let (parent, filename) = match path.rfind('/') {
+            Some(i) => (&path[..i.max(1)], &path[i + 1..]),
+            None => ("", path),
+        };
+        if !parent.is_empty() && sftp.stat(parent).is_err() {
+            if create_parents {
+                self.mkdir_all(parent)?;
+            } else {
+                return Err(Error::NotFound);
+            }
+        }
+        let temp_path = if parent.is_empty() {
+            format!(".{filename}.tmp-{}", Uuid::new_v4())
+        } else {
+            format!("{parent}/.{filename}.tmp-{}", Uuid::new_v4())
+        };
+        let mut temp = sftp.open(
+            &temp_path,
+            OpenFlags::WRITE_ONLY | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            mode,
+        )?;
+        if let Err(e) = temp.write_all(data) {
+            let _ = sftp.unlink(&temp_path);
+            return Err(e.into());
+        }
+        drop(temp);
+        if let Err(e) = sftp.rename(&temp_path, path) {
+            let _ = sftp.unlink(&temp_path);
+            return Err(e.into());
+        }
+        return Ok(());
+    }
+
+    /// Creates `path` and every missing ancestor directory, like `mkdir -p`. Used by
+    /// [`DeviceConnection::write_file`]'s `create_parents` option.
+    fn mkdir_all(&self, path: &str) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        let mut built = String::new();
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            built.push('/');
+            built.push_str(segment);
+            if sftp.stat(&built).is_err() {
+                sftp.mkdir(&built, 0o755)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Lists the contents of `path` over SFTP, skipping `.`/`..` and annotating entries with
+    /// the connected user's access permissions when known.
+    pub fn list_dir(&self, path: &str) -> Result<Vec<FileItem>, Error> {
+        let sftp = self.session.sftp()?;
+        let entries = sftp.read_dir(path)?;
+        let user = self.user.as_ref();
+        return Ok(entries
+            .iter()
+            .filter(|entry| entry.name() != Some(".") && entry.name() != Some(".."))
+            .map(|entry| FileItem::new(entry, None, user.map(|u| PermInfo::from(entry, u))))
+            .collect());
+    }
+
+    /// Moves/renames `from` to `to` over SFTP. Returns whether `to` already existed and was
+    /// overwritten. Plain SFTP rename refuses to cross filesystems on some servers; when that
+    /// happens this falls back to a remote-to-remote copy followed by deleting `from`.
+    pub fn rename(&self, from: &str, to: &str) -> Result<bool, Error> {
+        let sftp = self.session.sftp()?;
+        let overwrote = sftp.stat(to).is_ok();
+        match sftp.rename(from, to) {
+            Ok(_) => {}
+            Err(e) if format!("{e}").to_lowercase().contains("unsupported") => {
+                self.copy_remote(from, to)?;
+                sftp.unlink(from)?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        return Ok(overwrote);
+    }
+
+    /// Copies `from` to `to`, both remote paths, over SFTP. Used as the cross-filesystem
+    /// fallback for [`DeviceConnection::rename`].
+    fn copy_remote(&self, from: &str, to: &str) -> Result<u64, Error> {
+        let sftp = self.session.sftp()?;
+        let mut src = sftp.open(from, OpenFlags::READ_ONLY, 0)?;
+        let mut dst = sftp.open(
+            to,
+            OpenFlags::WRITE_ONLY | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            0o644,
+        )?;
+        return Ok(copy(&mut src, &mut dst)?);
+    }
+
+    /// Runs a cheap no-op command and returns how long it took, as a liveness probe the UI
+    /// can call before a batch operation instead of discovering staleness mid-batch.
+    pub fn ping(&self) -> Result<Duration, Error> {
+        let start = std::time::Instant::now();
+        self.exec("true", None)?;
+        return Ok(start.elapsed());
+    }
+
+    /// Lists running processes by walking `/proc` rather than shelling out to `ps`, since
+    /// BusyBox's `ps` varies across webOS builds in whether it exposes `cmdline` or RSS at
+    /// all. Processes that exit between the `stat` and `cmdline` reads are silently skipped
+    /// rather than erroring the whole call. Parsing itself is in [`parse_processes`].
+    pub fn list_processes(&self) -> Result<Vec<crate::conn_pool::ProcessInfo>, Error> {
+        let output = self.exec(
+            "for d in /proc/[0-9]*; do \
+               stat=$(cat \"$d/stat\" 2>/dev/null) || continue; \
+               cmdline=$(tr '\\0' ' ' < \"$d/cmdline\" 2>/dev/null); \
+               printf '%s\\t%s\\t%s\\n' \"${d#/proc/}\" \"$stat\" \"$cmdline\"; \
+             done",
+            None,
+        )?;
+        return Ok(parse_processes(&String::from_utf8_lossy(&output.stdout)));
+    }
+
+    /// Sends POSIX signal `sig` to `pid` via `kill`. `sig` is a fixed enum rather than a raw
+    /// string since it's interpolated directly into the remote shell command. Distinguishes
+    /// "no such process" so a caller killing a list of processes doesn't treat a
+    /// process that already exited as a hard failure.
+    pub fn kill(&self, pid: u32, sig: Sig) -> Result<(), Error> {
+        let command = format!("kill -{} {}", sig.as_str(), pid);
+        let output = self.exec(&command, None)?;
+        if output.status != 0 {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("No such process") {
+                return Err(Error::NotFound);
+            }
+            return Err(Error::exit_status(command, output.status, output.stderr, Some(self.id.to_string())));
+        }
+        return Ok(());
+    }
+
+    /// Probes which dev tools this device has and its webOS version, caching the result for
+    /// this connection's lifetime so repeated UI checks (e.g. "does this device support
+    /// `ares-install`?") don't each round-trip to the device. The probe itself is a handful
+    /// of `which`/`test` checks bundled into one `exec` call to keep it cheap the first time
+    /// it's needed — connections that never call this never pay for it at all.
+    pub fn capabilities(&self) -> Result<Capabilities, Error> {
+        if let Some(cached) = self.capabilities.lock().unwrap().clone() {
+            return Ok(cached);
+        }
+        let output = self.exec(
+            "for t in ares-install npm luna-send; do which $t >/dev/null 2>&1 && echo $t; done; \
+             cat /var/run/nyx/device_info.json 2>/dev/null || uname -r",
+            None,
+        )?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut webos_version = None;
+        let mut ares_install = false;
+        let mut npm = false;
+        let mut luna_send = false;
+        for line in stdout.lines() {
+            match line.trim() {
+                "ares-install" => ares_install = true,
+                "npm" => npm = true,
+                "luna-send" => luna_send = true,
+                other => {
+                    webos_version = serde_json::from_str::<serde_json::Value>(other)
+                        .ok()
+                        .and_then(|v| v.get("webos_build_id").and_then(|v| v.as_str()).map(String::from))
+                        .or_else(|| Some(String::from(other)).filter(|s| !s.is_empty()));
+                }
+            }
+        }
+        let capabilities = Capabilities {
+            webos_version,
+            ares_install,
+            npm,
+            luna_send,
+        };
+        *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+        return Ok(capabilities);
+    }
+
+    /// Reads and parses `/var/run/nyx/device_info.json` into a typed [`DeviceInfo`], falling
+    /// back to `uname -a` on devices old enough not to have nyx — in which case every field
+    /// comes back `None`. Extracted field-by-field via [`serde_json::Value`] rather than a
+    /// direct `Deserialize` impl, the same way [`crate::device_manager::DeviceProbe::parse`]
+    /// does: the device's JSON keys are `snake_case` (`model_name`, `webos_build_id`, ...)
+    /// while [`DeviceInfo`] serializes `camelCase` for the frontend, and a renamed/missing key
+    /// on some webOS version should degrade to `None` rather than fail the whole parse.
+    pub fn device_info(&self) -> Result<DeviceInfo, Error> {
+        let output = self.exec(
+            "cat /var/run/nyx/device_info.json 2>/dev/null || uname -a",
+            None,
+        )?;
+        let value = serde_json::from_slice::<serde_json::Value>(&output.stdout).ok();
+        let field = |name: &str| {
+            value
+                .as_ref()
+                .and_then(|v| v.get(name))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        return Ok(DeviceInfo {
+            model_name: field("model_name"),
+            webos_version: field("webos_build_id"),
+            sdk_version: field("sdk_version"),
+            board_type: field("board_type"),
+            device_arch: field("device_arch"),
+        });
+    }
+
+    /// Like [`DeviceConnection::exec`], but streams `stdin` from a `Read` in chunks instead of
+    /// requiring it all in memory upfront, for piping a large file into a remote command.
+    ///
+    /// This crate's channels are blocking `libssh_rs` handles rather than `tokio::io::AsyncRead`
+    /// (there's no async runtime on the connection-pool side), so the interleaving a true async
+    /// reader would need via `select!` is done here by alternating chunked reads of the local
+    /// source (each `read` of it blocks as `reader` itself does) with short-timeout drains of
+    /// remote stdout/stderr on every iteration — that
+    /// avoids the deadlock where the remote side fills its output buffers and blocks waiting for
+    /// us to drain it while we're still blocked writing stdin.
+    pub fn exec_with_reader<R: Read>(
+        &self,
+        command: &str,
+        mut reader: R,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_with_reader {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let mut in_buf = [0u8; 8192];
+        let mut out_buf = [0u8; 8192];
+        let mut stdin_done = false;
+        loop {
+            if !stdin_done {
+                let read = reader.read(&mut in_buf)?;
+                if read == 0 {
+                    ch.send_eof()?;
+                    stdin_done = true;
+                } else {
+                    ch.stdin().write_all(&in_buf[..read])?;
+                }
+            }
+            if ch.is_closed() || (ch.is_eof() && stdin_done) {
+                break;
+            }
+            let size = ch.read_timeout(&mut out_buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stdout.extend_from_slice(&out_buf[..size]);
+            }
+            let size = ch.read_timeout(&mut out_buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&out_buf[..size]);
+            }
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Stats `path` over SFTP, following symlinks. See [`DeviceConnection::lstat`] to stat the
+    /// symlink itself.
+    pub fn stat(&self, path: &str) -> Result<FileStat, Error> {
+        let sftp = self.session.sftp()?;
+        return Ok(FileStat::new(&sftp.stat(path)?, false));
+    }
+
+    /// Like [`DeviceConnection::stat`], but doesn't follow a symlink at `path`.
+    pub fn lstat(&self, path: &str) -> Result<FileStat, Error> {
+        let sftp = self.session.sftp()?;
+        let stat = sftp.lstat(path)?;
+        let is_symlink = stat.file_type() == Some(FileType::Symlink);
+        return Ok(FileStat::new(&stat, is_symlink));
+    }
+
+    /// Creates a symlink over SFTP pointing `link` at `target`. Some SFTP servers — BusyBox's
+    /// among them, which is what webOS ships — implement `SSH_FXP_SYMLINK`'s two path arguments
+    /// in the opposite order from what libssh sends, a long-standing quirk inherited from
+    /// OpenSSH's `sftp-server`. If the straightforward call fails, this retries once with the
+    /// arguments swapped before giving up, so callers don't need to know which interpretation
+    /// the far end uses.
+    pub fn symlink(&self, target: &str, link: &str) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        if let Err(e) = sftp.symlink(target, link) {
+            return Ok(sftp.symlink(link, target).map_err(|_| Error::from(e))?);
+        }
+        return Ok(());
+    }
+
+    /// Resolves the target of the symlink at `path` over SFTP, without following it further.
+    pub fn readlink(&self, path: &str) -> Result<String, Error> {
+        let sftp = self.session.sftp()?;
+        return Ok(sftp.read_link(path)?);
+    }
+
+    /// Changes the permission bits of `path` over SFTP.
+    pub fn chmod(&self, path: &str, mode: u32) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        sftp.chmod(path, mode)?;
+        return Ok(());
+    }
+
+    /// Creates a remote directory over SFTP with the given mode.
+    pub fn mkdir(&self, path: &str, mode: u32) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        sftp.mkdir(path, mode)?;
+        return Ok(());
+    }
+
+    /// Removes a single remote file (not a directory) over SFTP.
+    pub fn remove_file(&self, path: &str) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        sftp.unlink(path)?;
+        return Ok(());
+    }
+
+    /// Removes a remote directory over SFTP. When `recursive` is set, enumerates and deletes
+    /// children depth-first first; if any child fails to delete, deletion continues for the
+    /// rest and `Error::PartialFailure` lists every path that couldn't be removed.
+    pub fn remove_dir(&self, path: &str, recursive: bool) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        if recursive {
+            let mut failed = Vec::<String>::new();
+            for entry in sftp.read_dir(path)? {
+                let Some(name) = entry.name() else {
+                    continue;
+                };
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let child = format!("{}/{}", path.trim_end_matches('/'), name);
+                let result = if entry.file_type() == Some(FileType::Directory) {
+                    self.remove_dir(&child, true)
+                } else {
+                    self.remove_file(&child)
+                };
+                if result.is_err() {
+                    failed.push(child);
+                }
+            }
+            if !failed.is_empty() {
+                return Err(Error::PartialFailure { paths: failed });
+            }
+        }
+        sftp.rmdir(path)?;
+        return Ok(());
+    }
+
+    /// Builds a flat `path -> (size, mtime)` map of everything under `path`, descending into
+    /// subdirectories up to `max_depth` levels (`0` means `path`'s direct children only). Used
+    /// by [`DeviceConnection::poll_dir`] to diff two points in time against each other.
+    fn snapshot_dir_tree(
+        &self,
+        path: &str,
+        max_depth: u32,
+        snapshot: &mut HashMap<String, (u64, f64)>,
+    ) -> Result<(), Error> {
+        let sftp = self.session.sftp()?;
+        for entry in sftp.read_dir(path)? {
+            let Some(name) = entry.name() else {
+                continue;
+            };
+            if name == "." || name == ".." {
+                continue;
+            }
+            let child = format!("{}/{}", path.trim_end_matches('/'), name);
+            let mtime = entry
+                .modified()
+                .unwrap_or(std::time::UNIX_EPOCH)
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64();
+            snapshot.insert(child.clone(), (entry.len().unwrap_or(0), mtime));
+            if max_depth > 0 && entry.file_type() == Some(FileType::Directory) {
+                self.snapshot_dir_tree(&child, max_depth - 1, snapshot)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Polls `path` for changes every `interval`, calling `on_change` with whatever's different
+    /// since the previous snapshot (added/removed/modified, by size and mtime). Descends
+    /// `max_depth` levels into subdirectories, same as [`DeviceConnection::snapshot_dir_tree`].
+    /// Runs until `cancel` is tripped from another thread, the same cancellation handle
+    /// [`DeviceConnection::exec_cancellable`] uses, so a caller can stop watching without
+    /// tearing down the whole connection.
+    pub fn poll_dir<F: Fn(Vec<DirChange>)>(
+        &self,
+        path: &str,
+        interval: Duration,
+        max_depth: u32,
+        cancel: &CancelToken,
+        on_change: F,
+    ) -> Result<(), Error> {
+        let mut snapshot = HashMap::<String, (u64, f64)>::new();
+        self.snapshot_dir_tree(path, max_depth, &mut snapshot)?;
+        while !cancel.is_cancelled() {
+            std::thread::sleep(interval);
+            if cancel.is_cancelled() {
+                break;
+            }
+            let mut next = HashMap::<String, (u64, f64)>::new();
+            self.snapshot_dir_tree(path, max_depth, &mut next)?;
+            let mut changes = Vec::<DirChange>::new();
+            for (child, meta) in &next {
+                match snapshot.get(child) {
+                    None => changes.push(DirChange::Added { path: child.clone() }),
+                    Some(prev) if prev != meta => changes.push(DirChange::Modified { path: child.clone() }),
+                    _ => {}
+                }
+            }
+            for child in snapshot.keys() {
+                if !next.contains_key(child) {
+                    changes.push(DirChange::Removed { path: child.clone() });
+                }
+            }
+            if !changes.is_empty() {
+                on_change(changes);
+            }
+            snapshot = next;
+        }
+        return Ok(());
+    }
+
+    /// Like [`DeviceConnection::exec`], but invokes `on_data` for each chunk of stdout as it
+    /// arrives instead of buffering it, for long-running commands that stream logs. Stderr is
+    /// still buffered so it can be attached to the error on a non-zero exit.
+    pub fn exec_streaming<F: Fn(&[u8])>(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        on_data: F,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_streaming {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stderr = Vec::<u8>::new();
+        let mut buf = [0u8; 8192];
+        while !ch.is_closed() && !ch.is_eof() {
+            let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                on_data(&buf[..size]);
+            }
+            let size = ch.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&buf[..size]);
+            }
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout: Vec::new(),
+            stderr,
+            status,
+        });
+    }
+
+    /// Runs `grep pattern path` on the device and invokes `on_match` with each matching line as
+    /// it streams in, for searching a log too large to be worth downloading first. Built on
+    /// [`DeviceConnection::exec_streaming`], buffering stdout until each `\n` the same way
+    /// [`crate::session_manager::LineCallback`] does for a spawned [`crate::session_manager::Proc`].
+    /// `grep`'s exit status `1` ("no lines matched") is treated as success with zero matches
+    /// rather than an error; any other non-zero status is a real failure (bad pattern, missing
+    /// file, permission denied).
+    pub fn grep<F: Fn(&[u8])>(&self, pattern: &str, path: &str, on_match: F) -> Result<(), Error> {
+        let command = format!("grep -- {} {}", shell_quote(pattern), shell_quote(path));
+        let buf = std::cell::RefCell::new(Vec::<u8>::new());
+        let output = self.exec_streaming(&command, None, |chunk| {
+            let mut buf = buf.borrow_mut();
+            buf.extend_from_slice(chunk);
+            while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                on_match(&line[..line.len() - 1]);
+            }
+        })?;
+        let remaining = buf.into_inner();
+        if !remaining.is_empty() {
+            on_match(&remaining);
+        }
+        return match output.status {
+            0 | 1 => Ok(()),
+            status => Err(Error::exit_status(command, status, output.stderr, Some(self.id().to_string()))),
+        };
+    }
+
+    /// Like [`DeviceConnection::exec`], but gives up and returns [`Error::Timeout`] if the
+    /// command doesn't finish within `timeout`. The channel is closed before returning so
+    /// the connection isn't left with a dangling exec; callers get a hung device evicted
+    /// from the pool automatically, since `mark_last_ok` is never reached on this path.
+    pub fn exec_timeout(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_timeout {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let deadline = std::time::Instant::now() + timeout;
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let mut buf = [0u8; 8192];
+        while !ch.is_closed() && !ch.is_eof() {
+            if std::time::Instant::now() >= deadline {
+                ch.close()?;
+                return Err(Error::Timeout);
+            }
+            let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stdout.extend_from_slice(&buf[..size]);
+            }
+            let size = ch.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&buf[..size]);
+            }
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Like [`DeviceConnection::exec_timeout`], but bounds the command's total wall-clock
+    /// runtime rather than giving up client-side: once `deadline` passes, it signals `TERM` to
+    /// the remote process and gives it `kill_grace` to exit before escalating to `KILL`, then
+    /// returns [`Error::DeadlineExceeded`] once the channel actually closes. Use this instead of
+    /// `exec_timeout` when a stuck remote process (not just a slow client-side read) needs to
+    /// be bounded hard, e.g. CI-style automation.
+    pub fn exec_deadline(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        deadline: std::time::Instant,
+        kill_grace: Duration,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_deadline {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let mut buf = [0u8; 8192];
+        let mut term_sent_at: Option<std::time::Instant> = None;
+        while !ch.is_closed() && !ch.is_eof() {
+            match term_sent_at {
+                None if std::time::Instant::now() >= deadline => {
+                    log::warn!("{:?} exec_deadline exceeded, sending TERM", self.id);
+                    ch.request_send_signal("TERM")?;
+                    term_sent_at = Some(std::time::Instant::now());
+                }
+                Some(sent_at) if sent_at.elapsed() >= kill_grace => {
+                    log::warn!("{:?} exec_deadline grace elapsed, sending KILL", self.id);
+                    ch.request_send_signal("KILL")?;
+                    ch.close()?;
+                    return Err(Error::DeadlineExceeded);
+                }
+                _ => {}
+            }
+            let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stdout.extend_from_slice(&buf[..size]);
+            }
+            let size = ch.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&buf[..size]);
+            }
+        }
+        if term_sent_at.is_some() {
+            ch.close()?;
+            return Err(Error::DeadlineExceeded);
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// Like [`DeviceConnection::exec`], but gives up and returns [`Error::Cancelled`] as soon
+    /// as `cancel` is tripped, closing the channel rather than letting the command keep holding
+    /// it. `cancel` is polled the same way [`DeviceConnection::exec_timeout`] polls its
+    /// deadline, so cancellation latency is bounded by the same 10ms read timeout.
+    pub fn exec_cancellable(
+        &self,
+        command: &str,
+        stdin: Option<&[u8]>,
+        cancel: &CancelToken,
+    ) -> Result<ExecOutput, Error> {
+        log::debug!("{:?} exec_cancellable {:?}", self.id, redact_secrets(command));
+        let _permit = self.acquire_channel_permit();
+        let ch = self.session.new_channel()?;
+        ch.open_session()?;
+        ch.request_exec(command)?;
+        if let Some(stdin) = stdin {
+            ch.stdin().write_all(stdin)?;
+            ch.send_eof()?;
+        }
+        let mut stdout = Vec::<u8>::new();
+        let mut stderr = Vec::<u8>::new();
+        let mut buf = [0u8; 8192];
+        while !ch.is_closed() && !ch.is_eof() {
+            if cancel.is_cancelled() {
+                ch.close()?;
+                return Err(Error::Cancelled);
+            }
+            let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stdout.extend_from_slice(&buf[..size]);
+            }
+            let size = ch.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if size > 0 {
+                stderr.extend_from_slice(&buf[..size]);
+            }
+        }
+        let status = Self::exit_status(&ch)?;
+        ch.close()?;
+        return Ok(ExecOutput {
+            stdout,
+            stderr,
+            status,
+        });
+    }
+
+    /// SHA-256 fingerprint of the server's host key, hex-encoded. Compared against
+    /// `Device::host_key_fingerprint` on connect when one has been trusted, and used by
+    /// `DeviceManager::trust_host_key` to record a new one.
+    pub(crate) fn host_key_fingerprint(session: &Session) -> Result<String, Error> {
+        let key = session.get_server_public_key()?;
+        return Ok(key.get_public_key_hash_hexa(PublicKeyHashType::Sha256)?);
+    }
+
     pub(crate) fn session_init(session: &Session) -> Result<(), Error> {
         let kex = vec![
             "curve25519-sha256",
@@ -124,6 +1572,193 @@ impl DeviceConnection {
     }
 }
 
+/// Parses busybox `df -k`'s output — not `df --output`, which GNU coreutils supports but
+/// busybox (what webOS ships) doesn't. Busybox's column layout also isn't fixed width across
+/// webOS versions (it wraps long device names onto their own line), so this parses by splitting
+/// on whitespace and skipping the header rather than fixed offsets, same approach as the
+/// frontend's existing `df` parsing in `DeviceManagerService.getStorageInfo`. Pulled out of
+/// [`DeviceConnection::disk_usage`] so it can be tested against a string directly.
+fn parse_df_output(text: &str) -> Vec<DiskUsage> {
+    let mut lines = text.lines();
+    lines.next(); // header: "Filesystem  1K-blocks  Used  Available  Use%  Mounted on"
+    let mut usages = Vec::new();
+    let mut pending: Option<String> = None;
+    for line in lines {
+        let mut segs: Vec<&str> = line.split_whitespace().collect();
+        // A device name too long to fit its column wraps onto its own line, pushing the
+        // numeric columns onto the next — stash it and prepend it once those arrive.
+        if segs.len() == 1 {
+            pending = Some(segs[0].to_string());
+            continue;
+        }
+        if let Some(device) = pending.take() {
+            segs.insert(0, &device);
+        }
+        if segs.len() < 6 {
+            continue;
+        }
+        let (total, used, available, mount) = match (
+            segs[1].parse::<u64>(),
+            segs[2].parse::<u64>(),
+            segs[3].parse::<u64>(),
+        ) {
+            (Ok(total), Ok(used), Ok(available)) => (total, used, available, segs[5]),
+            _ => continue,
+        };
+        usages.push(DiskUsage {
+            mount: mount.to_string(),
+            total: total * 1024,
+            used: used * 1024,
+            available: available * 1024,
+        });
+    }
+    return usages;
+}
+
+/// Parses the `pid\t/proc/[pid]/stat\tcmdline` lines produced by
+/// [`DeviceConnection::list_processes`]'s shell loop. `comm` (the second `stat` field) is
+/// parenthesized and may itself contain spaces, so this finds it by splitting on the last `)`
+/// rather than whitespace. Lines that don't parse (e.g. a process that exited between the
+/// `stat` and `cmdline` reads, leaving a malformed row) are silently skipped rather than
+/// erroring the whole call. Pulled out of `list_processes` so it can be tested against a string
+/// directly.
+fn parse_processes(stdout: &str) -> Vec<crate::conn_pool::ProcessInfo> {
+    let mut processes = Vec::new();
+    for line in stdout.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let (Some(pid), Some(stat), cmdline) =
+            (parts.next(), parts.next(), parts.next().unwrap_or(""))
+        else {
+            continue;
+        };
+        let Ok(pid) = pid.parse::<u32>() else {
+            continue;
+        };
+        let Some(open) = stat.find('(') else { continue };
+        let Some(close) = stat.rfind(')') else {
+            continue;
+        };
+        let name = String::from(&stat[open + 1..close]);
+        let rest: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+        let rss_pages = rest.get(21).and_then(|s| s.parse::<u64>().ok()).unwrap_or(0);
+        processes.push(crate::conn_pool::ProcessInfo {
+            pid,
+            name,
+            cmdline: String::from(cmdline.trim()),
+            rss: rss_pages * 4096,
+        });
+    }
+    return processes;
+}
+
+/// Masks values that look like they follow a `password`/`passphrase`/`-p`-style flag, so
+/// debug logs of a command line are safe to paste into a bug report. This is a best-effort
+/// heuristic over common CLI conventions (e.g. `luna-send ... '{"password":"..."}'`), not a
+/// guarantee every secret is caught — callers that know a command embeds a secret should
+/// prefer [`DeviceConnection::exec_secret`] instead of relying on redaction.
+pub(crate) fn redact_secrets(command: &str) -> String {
+    let secret_like = Regex::new(
+        r#"(?i)("?(?:password|passphrase|passwd|secret|token)"?\s*[:=]\s*"?)[^"'\s,}]+"#,
+    )
+    .unwrap();
+    return secret_like.replace_all(command, "$1<redacted>").into_owned();
+}
+
+/// Output cap for [`DeviceConnection::read_tail`] — generous for a log excerpt, but still a
+/// hard ceiling in case a requested line count lands on an unexpectedly wide line.
+const MAX_TAIL_BYTES: usize = 1024 * 1024;
+
+/// Recognized `ares-install`-style failure tokens, in the order they're checked. webOS CLI
+/// tools print these as bare words somewhere in stderr rather than as a structured error, so
+/// this looks for the first one present rather than trying to parse a fixed format.
+const INSTALLER_ERROR_TOKENS: &[&str] = &[
+    "FAILED_REMOVE",
+    "INVALID_PACKAGE",
+    "INSTALL_FAILED",
+    "NOT_ENOUGH_STORAGE",
+    "PACKAGE_NOT_FOUND",
+];
+
+/// Scans `stderr` for a known webOS installer failure token, returning [`Error::WebosTool`]
+/// with the token as `code` and the line it appeared on as `detail`. Returns `None` if nothing
+/// recognizable is found, so the caller can fall back to a generic exit-status error.
+fn classify_installer_error(stderr: &[u8]) -> Option<Error> {
+    let text = String::from_utf8_lossy(stderr);
+    for line in text.lines() {
+        for token in INSTALLER_ERROR_TOKENS {
+            if line.contains(token) {
+                return Some(Error::WebosTool {
+                    code: token.to_string(),
+                    detail: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    return None;
+}
+
+/// How [`DeviceConnection::exec_elevated`] will get root for a given connection.
+enum ElevationMethod {
+    AlreadyRoot,
+    Sudo,
+}
+
+/// Wraps `s` in single quotes for safe embedding in a remote shell command, escaping any
+/// single quotes it already contains the usual POSIX way (close the quote, emit an escaped
+/// quote, reopen it).
+fn shell_quote(s: &str) -> String {
+    return format!("'{}'", s.replace('\'', "'\"'\"'"));
+}
+
+pub(crate) struct ChannelPermit {
+    gate: Arc<(Mutex<u32>, Condvar)>,
+}
+
+impl Drop for ChannelPermit {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.gate;
+        *lock.lock().expect("Failed to lock DeviceConnection::channel_gate") += 1;
+        cvar.notify_one();
+    }
+}
+
+/// Like [`std::io::copy`], but when `rate_limit` (bytes/sec) is set, sleeps between chunks to
+/// stay roughly at that rate. Reads in 64KiB chunks rather than the whole file at once so the
+/// throttle is smooth instead of bursty.
+fn copy_throttled<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    rate_limit: Option<u64>,
+) -> Result<u64, Error> {
+    const CHUNK: usize = 64 * 1024;
+    let Some(rate_limit) = rate_limit.filter(|r| *r > 0) else {
+        return Ok(copy(reader, writer)?);
+    };
+    let mut buf = vec![0u8; CHUNK];
+    let mut total = 0u64;
+    let window = Duration::from_secs(1);
+    let mut window_start = std::time::Instant::now();
+    let mut window_bytes = 0u64;
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buf[..read])?;
+        total += read as u64;
+        window_bytes += read as u64;
+        if window_bytes >= rate_limit {
+            let elapsed = window_start.elapsed();
+            if elapsed < window {
+                std::thread::sleep(window - elapsed);
+            }
+            window_start = std::time::Instant::now();
+            window_bytes = 0;
+        }
+    }
+    return Ok(total);
+}
+
 impl Deref for DeviceConnection {
     type Target = Session;
 
@@ -147,6 +1782,9 @@ impl Drop for DeviceConnection {
                 .lock()
                 .expect("Failed to lock DeviceConnection::last_ok")
         );
+        // Best-effort: send a clean SSH disconnect instead of just letting the socket drop,
+        // so the device doesn't think the session is still open.
+        let _ = self.session.disconnect();
     }
 }
 
@@ -234,3 +1872,288 @@ impl Id {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_wraps_plain_string() {
+        assert_eq!(shell_quote("hello"), "'hello'");
+    }
+
+    #[test]
+    fn shell_quote_escapes_embedded_single_quotes() {
+        assert_eq!(shell_quote("it's"), "'it'\"'\"'s'");
+    }
+
+    #[test]
+    fn redact_secrets_masks_key_value_password() {
+        let redacted = redact_secrets("luna-send -n 1 com.webos.service.x '{\"password\":\"hunter2\"}'");
+        assert!(!redacted.contains("hunter2"));
+        assert!(redacted.contains("<redacted>"));
+        assert!(redacted.contains("luna-send -n 1 com.webos.service.x"));
+    }
+
+    #[test]
+    fn redact_secrets_leaves_unrelated_text_untouched() {
+        let command = "ls -la /media/developer";
+        assert_eq!(redact_secrets(command), command);
+    }
+
+    #[test]
+    fn classify_installer_error_recognizes_known_token() {
+        let err = classify_installer_error(b"Installing...\nERR! NOT_ENOUGH_STORAGE: 10MB required")
+            .expect("expected a classified error");
+        match err {
+            Error::WebosTool { code, detail } => {
+                assert_eq!(code, "NOT_ENOUGH_STORAGE");
+                assert!(detail.contains("NOT_ENOUGH_STORAGE"));
+            }
+            _ => panic!("expected Error::WebosTool"),
+        }
+    }
+
+    #[test]
+    fn classify_installer_error_returns_none_when_no_token_present() {
+        assert!(classify_installer_error(b"Installing...\nDone.").is_none());
+    }
+
+    #[test]
+    fn parse_df_output_parses_normal_rows() {
+        let text = "Filesystem           1K-blocks      Used Available Use% Mounted on\n\
+                     /dev/root               512000    256000    256000  50% /\n\
+                     tmpfs                    65536      1024     64512   2% /tmp\n";
+        let usages = parse_df_output(text);
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].mount, "/");
+        assert_eq!(usages[0].total, 512000 * 1024);
+        assert_eq!(usages[0].used, 256000 * 1024);
+        assert_eq!(usages[0].available, 256000 * 1024);
+        assert_eq!(usages[1].mount, "/tmp");
+    }
+
+    #[test]
+    fn parse_df_output_reassembles_wrapped_device_name_rows() {
+        let text = "Filesystem           1K-blocks      Used Available Use% Mounted on\n\
+                     /dev/mapper/very-long-volume-group-name-root\n\
+                        512000    256000    256000  50% /\n";
+        let usages = parse_df_output(text);
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].mount, "/");
+    }
+
+    #[test]
+    fn parse_df_output_skips_short_malformed_rows() {
+        let text = "Filesystem           1K-blocks      Used Available Use% Mounted on\n\
+                     garbage line\n";
+        assert!(parse_df_output(text).is_empty());
+    }
+
+    #[test]
+    fn parse_processes_parses_well_formed_line_with_spaces_in_comm() {
+        let stdout = "123\t123 (my app) S 1 123 123 0 -1 4194304 100 0 0 0 10 5 0 0 20 0 1 0 1000 4096 256 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0\t/usr/bin/my app --flag\n";
+        let processes = parse_processes(stdout);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, 123);
+        assert_eq!(processes[0].name, "my app");
+        assert_eq!(processes[0].cmdline, "/usr/bin/my app --flag");
+        assert_eq!(processes[0].rss, 256 * 4096);
+    }
+
+    #[test]
+    fn parse_processes_skips_malformed_lines() {
+        let stdout = "not-a-pid\tsome stat\tsome cmdline\n";
+        assert!(parse_processes(stdout).is_empty());
+    }
+
+    mod mock_server {
+        //! A scriptable, in-process `russh` SSH server `exec` is run against in place of a real
+        //! device, so its stdout/stderr/exit-status handling (and the order it reads them in)
+        //! is covered by a deterministic test instead of only ever being exercised manually
+        //! against a TV. Written against `russh`/`russh-keys` 0.44's `server::Handler` API.
+
+        use super::*;
+        use russh::server::{Auth, Handler, Msg, Server as _, Session};
+        use russh::{Channel, ChannelId, CryptoVec};
+        use russh_keys::key::KeyPair;
+        use std::net::SocketAddr;
+        use std::sync::mpsc;
+        use tokio::net::TcpListener;
+
+        /// What a mock channel should send back for any `exec` it receives.
+        #[derive(Clone)]
+        pub(super) struct ScriptedExec {
+            pub stdout: Vec<u8>,
+            pub stderr: Vec<u8>,
+            pub exit_code: u32,
+        }
+
+        #[derive(Clone)]
+        struct MockServer {
+            script: ScriptedExec,
+        }
+
+        impl russh::server::Server for MockServer {
+            type Handler = MockHandler;
+
+            fn new_client(&mut self, _addr: Option<SocketAddr>) -> MockHandler {
+                return MockHandler {
+                    script: self.script.clone(),
+                };
+            }
+        }
+
+        struct MockHandler {
+            script: ScriptedExec,
+        }
+
+        #[async_trait::async_trait]
+        impl Handler for MockHandler {
+            type Error = russh::Error;
+
+            async fn auth_none(&mut self, _user: &str) -> Result<Auth, Self::Error> {
+                // Nothing under test cares about authentication; accept unconditionally so the
+                // scripted exec is reachable without a password/key fixture.
+                return Ok(Auth::Accept);
+            }
+
+            async fn channel_open_session(
+                &mut self,
+                _channel: Channel<Msg>,
+                _session: &mut Session,
+            ) -> Result<bool, Self::Error> {
+                return Ok(true);
+            }
+
+            async fn exec_request(
+                &mut self,
+                channel: ChannelId,
+                _data: &[u8],
+                session: &mut Session,
+            ) -> Result<(), Self::Error> {
+                // Same order `DeviceConnection::exec` reads in: all of stdout, then all of
+                // stderr, then EOF, then the exit status, then close. A regression that read
+                // these out of order (e.g. blocking on stderr before stdout was drained) would
+                // hang or misattribute bytes here exactly like it would against a real device.
+                session.data(channel, CryptoVec::from(self.script.stdout.clone()));
+                session.extended_data(channel, 1, CryptoVec::from(self.script.stderr.clone()));
+                session.eof(channel);
+                session.exit_status_request(channel, self.script.exit_code);
+                session.close(channel);
+                return Ok(());
+            }
+        }
+
+        /// Starts a `MockServer` scripted with `script` on an OS-assigned loopback port and
+        /// blocks until it's ready to accept, returning the port for [`DeviceConnection::new`]
+        /// to dial exactly like it would a real device.
+        pub(super) fn spawn(script: ScriptedExec) -> u16 {
+            let (port_tx, port_rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let runtime = tokio::runtime::Runtime::new().expect("mock ssh server runtime");
+                runtime.block_on(async move {
+                    let listener = TcpListener::bind("127.0.0.1:0")
+                        .await
+                        .expect("bind mock ssh server");
+                    port_tx.send(listener.local_addr().unwrap().port()).unwrap();
+                    let mut config = russh::server::Config::default();
+                    config.keys = vec![KeyPair::generate_ed25519().expect("generate host key")];
+                    let config = std::sync::Arc::new(config);
+                    let mut server = MockServer { script };
+                    // A single connection is all any one test makes; there's nothing to loop
+                    // for beyond the one `exec` each test scripts.
+                    let (socket, _) = listener.accept().await.expect("accept mock ssh client");
+                    russh::server::run_stream(config, socket, server.new_client(None))
+                        .await
+                        .ok();
+                });
+            });
+            return port_rx.recv().expect("mock ssh server bound a port");
+        }
+    }
+
+    fn mock_device(port: u16) -> Device {
+        return Device {
+            order: None,
+            default: None,
+            profile: String::from("ose"),
+            name: String::from("mock"),
+            description: None,
+            host: String::from("127.0.0.1"),
+            port,
+            username: String::from("test"),
+            new: false,
+            private_key: None,
+            files: None,
+            passphrase: None,
+            password: None,
+            log_daemon: None,
+            no_port_forwarding: None,
+            indelible: None,
+            host_key_fingerprint: None,
+            use_agent: None,
+            connect_timeout_ms: Some(2000),
+            compression: None,
+            idle_timeout_secs: None,
+            max_channels: None,
+            shared_connection: None,
+            jump_host: None,
+        };
+    }
+
+    /// Connects to `device`, trusting whatever host key the mock server happens to present —
+    /// it's a freshly generated key every run, so there's nothing meaningful to pin against —
+    /// the same way a real caller would after confirming [`Error::UnknownHostKey`] once.
+    fn connect_trusting(device: Device) -> Result<DeviceConnection, Error> {
+        return match DeviceConnection::new(device.clone(), None) {
+            Err(Error::UnknownHostKey { fingerprint }) => {
+                let mut device = device;
+                device.host_key_fingerprint = Some(fingerprint);
+                DeviceConnection::new(device, None)
+            }
+            other => other,
+        };
+    }
+
+    #[test]
+    fn exec_captures_stdout_stderr_and_exit_status_in_order() {
+        let port = mock_server::spawn(mock_server::ScriptedExec {
+            stdout: b"hello stdout".to_vec(),
+            stderr: b"hello stderr".to_vec(),
+            exit_code: 0,
+        });
+        let conn = connect_trusting(mock_device(port)).expect("connect to mock ssh server");
+        let output = conn.exec("anything", None).expect("exec against mock server");
+        assert_eq!(output.stdout, b"hello stdout");
+        assert_eq!(output.stderr, b"hello stderr");
+        assert_eq!(output.status, 0);
+    }
+
+    #[test]
+    fn exec_reports_non_zero_exit_status() {
+        let port = mock_server::spawn(mock_server::ScriptedExec {
+            stdout: Vec::new(),
+            stderr: b"boom".to_vec(),
+            exit_code: 1,
+        });
+        let conn = connect_trusting(mock_device(port)).expect("connect to mock ssh server");
+        let output = conn.exec("anything", None).expect("exec against mock server");
+        assert_eq!(output.status, 1);
+        assert_eq!(output.stderr, b"boom");
+    }
+
+    #[test]
+    fn exec_captures_stderr_separately_from_stdout_on_success() {
+        let port = mock_server::spawn(mock_server::ScriptedExec {
+            stdout: b"ok".to_vec(),
+            stderr: b"warning: deprecated".to_vec(),
+            exit_code: 0,
+        });
+        let conn = connect_trusting(mock_device(port)).expect("connect to mock ssh server");
+        let output = conn.exec("anything", None).expect("exec against mock server");
+        assert_eq!(output.status, 0);
+        assert_eq!(output.stdout, b"ok");
+        assert_eq!(output.stderr, b"warning: deprecated");
+    }
+}
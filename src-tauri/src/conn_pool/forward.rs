@@ -0,0 +1,381 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use libssh_rs::{AuthStatus, Session, SshKey, SshOption};
+
+use crate::conn_pool::DeviceConnection;
+use crate::device_manager::{Device, JumpHostConfig};
+use crate::error::Error;
+
+/// Bastion hop for `DeviceConnection::new` when `Device::jump_host` is set: connects to the
+/// bastion, opens a `direct-tcpip` channel to the real target, and exposes it as a local
+/// loopback port so the target `Session` can `connect()` to it like any other host, tunneling
+/// the whole handshake (including the target's own host key check) through the bastion. Kept
+/// alive for as long as the `DeviceConnection` that owns it — dropping it tears down the pump
+/// thread and, with it, the bastion connection.
+pub(crate) struct JumpTunnel {
+    pub local_port: u16,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl JumpTunnel {
+    pub(crate) fn open(
+        jump: &JumpHostConfig,
+        ssh_dir: Option<&Path>,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<JumpTunnel, Error> {
+        let hop = |e: Error| Error::JumpHostFailed {
+            hop: String::from("bastion"),
+            message: e.to_string(),
+        };
+        let bastion = Session::new().map_err(Error::from).map_err(hop)?;
+        DeviceConnection::session_init(&bastion).map_err(hop)?;
+        bastion
+            .set_option(SshOption::Hostname(jump.host.clone()))
+            .map_err(Error::from)
+            .map_err(hop)?;
+        bastion
+            .set_option(SshOption::Port(jump.port))
+            .map_err(Error::from)
+            .map_err(hop)?;
+        bastion
+            .set_option(SshOption::User(Some(jump.username.clone())))
+            .map_err(Error::from)
+            .map_err(hop)?;
+        bastion.connect().map_err(Error::from).map_err(hop)?;
+
+        if let Some(private_key) = &jump.private_key {
+            let priv_key_content = private_key.content(ssh_dir).map_err(hop)?;
+            let priv_key =
+                SshKey::from_privkey_base64(&priv_key_content, jump.passphrase.as_deref())
+                    .map_err(|_| {
+                        hop(if jump.passphrase.is_none() {
+                            Error::PassphraseRequired
+                        } else {
+                            Error::BadPassphrase
+                        })
+                    })?;
+            if bastion
+                .userauth_publickey(None, &priv_key)
+                .map_err(Error::from)
+                .map_err(hop)?
+                != AuthStatus::Success
+            {
+                return Err(hop(Error::AuthFailed {
+                    methods_tried: vec![String::from("publickey")],
+                }));
+            }
+        } else if let Some(password) = &jump.password {
+            if bastion
+                .userauth_password(None, Some(password))
+                .map_err(Error::from)
+                .map_err(hop)?
+                != AuthStatus::Success
+            {
+                return Err(hop(Error::AuthFailed {
+                    methods_tried: vec![String::from("password")],
+                }));
+            }
+        } else {
+            return Err(hop(Error::AuthMethodsExhausted));
+        }
+
+        let channel = bastion.new_channel().map_err(Error::from).map_err(hop)?;
+        channel
+            .open_forward(target_host, target_port as u32, "127.0.0.1", 0)
+            .map_err(Error::from)
+            .map_err(hop)?;
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let local_port = listener.local_addr()?.port();
+        listener.set_nonblocking(true)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = stop.clone();
+        let worker = std::thread::spawn(move || {
+            // `bastion` and `channel` must outlive the pump loop, so they're moved in rather
+            // than dropped at the end of `open` — that's the whole point of this thread.
+            let _bastion = bastion;
+            loop {
+                if worker_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = pump(&stream, &channel) {
+                            log::warn!("jump_host: tunnel closed with {e:?}");
+                        }
+                        // The target `Session` opens exactly one TCP connection through this
+                        // listener; once it's done with the tunnel there's nothing left to serve.
+                        break;
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(e) => {
+                        log::warn!("jump_host: accept failed: {e:?}");
+                        break;
+                    }
+                }
+            }
+        });
+        return Ok(JumpTunnel {
+            local_port,
+            stop,
+            worker: Some(worker),
+        });
+    }
+}
+
+impl Drop for JumpTunnel {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.join().unwrap_or(());
+        }
+    }
+}
+
+/// Handle to a local port forward opened by [`PortForward::open_local`]. Dropping it
+/// stops accepting new local connections and tears down the background thread; connections
+/// already in flight are allowed to drain.
+pub struct PortForward {
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl PortForward {
+    /// Opens `local_port` on `127.0.0.1` and, for every client that connects to it, opens a
+    /// `direct-tcpip` channel to `remote_host:remote_port` on the device and pumps bytes in
+    /// both directions. Each local client gets its own channel, but clients are served one at
+    /// a time on the forward's single background thread: a client that connects while another
+    /// client's channel is open waits until that channel closes.
+    pub fn open_local(
+        device: Device,
+        ssh_dir: Option<&Path>,
+        local_port: u16,
+        remote_host: String,
+        remote_port: u16,
+    ) -> Result<PortForward, Error> {
+        let listener = TcpListener::bind(("127.0.0.1", local_port))?;
+        listener.set_nonblocking(true)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let ssh_dir = ssh_dir.map(|p| p.to_path_buf());
+        let worker_stop = stop.clone();
+        let worker = std::thread::spawn(move || {
+            let connection = match DeviceConnection::new(device, ssh_dir.as_deref()) {
+                Ok(c) => c,
+                Err(e) => {
+                    log::warn!("forward_local failed to connect: {e:?}");
+                    return;
+                }
+            };
+            while !worker_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, addr)) => {
+                        log::debug!("forward_local: accepted {addr:?}");
+                        match connection.new_channel().and_then(|ch| {
+                            ch.open_forward(&remote_host, remote_port as u32, "127.0.0.1", local_port as u32)?;
+                            Ok(ch)
+                        }) {
+                            Ok(ch) => {
+                                if let Err(e) = pump(&stream, &ch) {
+                                    log::warn!("forward_local: channel closed with {e:?}");
+                                }
+                            }
+                            Err(e) => log::warn!("forward_local: failed to open channel: {e:?}"),
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(e) => {
+                        log::warn!("forward_local: accept failed: {e:?}");
+                        break;
+                    }
+                }
+            }
+        });
+        return Ok(PortForward {
+            stop,
+            worker: Some(worker),
+        });
+    }
+    /// Asks the device to listen on `remote_port` and forward every connection it accepts back
+    /// to `local_host:local_port` on the desktop, i.e. a reverse tunnel. Returns an `Error` if
+    /// the device refuses the forward request (webOS sometimes disallows privileged ports).
+    pub fn open_remote(
+        device: Device,
+        ssh_dir: Option<&Path>,
+        remote_port: u16,
+        local_host: String,
+        local_port: u16,
+    ) -> Result<PortForward, Error> {
+        let connection = DeviceConnection::new(device, ssh_dir)?;
+        connection.listen_forward(Some("0.0.0.0"), remote_port as i32)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker_stop = stop.clone();
+        let worker = std::thread::spawn(move || loop {
+            if worker_stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let (_, ch) = match connection.accept_forward(Duration::from_millis(100)) {
+                Ok(res) => res,
+                Err(_) => continue,
+            };
+            match TcpStream::connect((local_host.as_str(), local_port)) {
+                Ok(stream) => {
+                    if let Err(e) = pump(&stream, &ch) {
+                        log::warn!("forward_remote: channel closed with {e:?}");
+                    }
+                }
+                Err(e) => log::warn!("forward_remote: failed to dial local target: {e:?}"),
+            }
+        });
+        return Ok(PortForward {
+            stop,
+            worker: Some(worker),
+        });
+    }
+
+    /// Opens `local_port` on `127.0.0.1` and runs a minimal SOCKS5 server on it: a client
+    /// connects, negotiates no-auth, issues a `CONNECT` for an IPv4 or domain-name
+    /// destination, and every accepted socket gets its own `direct-tcpip` channel to that
+    /// destination — i.e. a dynamic forward, unlike [`PortForward::open_local`]'s fixed
+    /// remote endpoint. Only `CONNECT` is supported; `BIND`/`UDP ASSOCIATE` are rejected.
+    pub fn open_socks(
+        device: Device,
+        ssh_dir: Option<&Path>,
+        local_port: u16,
+    ) -> Result<PortForward, Error> {
+        let listener = TcpListener::bind(("127.0.0.1", local_port))?;
+        listener.set_nonblocking(true)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let ssh_dir = ssh_dir.map(|p| p.to_path_buf());
+        let worker_stop = stop.clone();
+        let worker = std::thread::spawn(move || {
+            let connection = match DeviceConnection::new(device, ssh_dir.as_deref()) {
+                Ok(c) => c,
+                Err(e) => {
+                    log::warn!("forward_socks failed to connect: {e:?}");
+                    return;
+                }
+            };
+            while !worker_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, addr)) => {
+                        log::debug!("forward_socks: accepted {addr:?}");
+                        match socks5_handshake(&stream).and_then(|(host, port)| {
+                            let ch = connection.new_channel()?;
+                            ch.open_forward(&host, port as u32, "127.0.0.1", local_port as u32)?;
+                            Ok(ch)
+                        }) {
+                            Ok(ch) => {
+                                if let Err(e) = pump(&stream, &ch) {
+                                    log::warn!("forward_socks: channel closed with {e:?}");
+                                }
+                            }
+                            Err(e) => log::warn!("forward_socks: failed to set up channel: {e:?}"),
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(e) => {
+                        log::warn!("forward_socks: accept failed: {e:?}");
+                        break;
+                    }
+                }
+            }
+        });
+        return Ok(PortForward {
+            stop,
+            worker: Some(worker),
+        });
+    }
+}
+
+/// Runs the server side of a SOCKS5 handshake (RFC 1928) far enough to extract the
+/// requested destination: no-auth method negotiation, then a `CONNECT` request with an
+/// IPv4 or domain-name address. Replies `succeeded` optimistically before the `direct-tcpip`
+/// channel is actually open, matching how most SOCKS5 clients treat the reply as advisory.
+fn socks5_handshake(stream: &TcpStream) -> Result<(String, u16), Error> {
+    let mut stream = stream.try_clone()?;
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header)?;
+    if header[0] != 0x05 {
+        return Err(Error::new("unsupported SOCKS version"));
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods)?;
+    stream.write_all(&[0x05, 0x00])?; // no auth required
+
+    let mut req = [0u8; 4];
+    stream.read_exact(&mut req)?;
+    if req[0] != 0x05 || req[1] != 0x01 {
+        // only CONNECT (0x01) is supported
+        stream.write_all(&[0x05, 0x07, 0x00, 0x01, 0, 0, 0, 0, 0, 0])?;
+        return Err(Error::new("unsupported SOCKS command"));
+    }
+    let host = match req[3] {
+        0x01 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr)?;
+            format!("{}.{}.{}.{}", addr[0], addr[1], addr[2], addr[3])
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name)?;
+            String::from_utf8(name).map_err(|_| Error::new("invalid SOCKS domain name"))?
+        }
+        _ => {
+            stream.write_all(&[0x05, 0x08, 0x00, 0x01, 0, 0, 0, 0, 0, 0])?;
+            return Err(Error::new("unsupported SOCKS address type"));
+        }
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port)?;
+    let port = u16::from_be_bytes(port);
+
+    stream.write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])?;
+    return Ok((host, port));
+}
+
+pub(crate) fn pump(stream: &std::net::TcpStream, ch: &libssh_rs::Channel) -> Result<(), Error> {
+    stream.set_read_timeout(Some(Duration::from_millis(10)))?;
+    let mut stream = stream.try_clone()?;
+    let mut buf = [0u8; 8192];
+    while !ch.is_closed() && !ch.is_eof() {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => ch.stdin().write_all(&buf[..n])?,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.into()),
+        }
+        let size = ch.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+        if size > 0 {
+            stream.write_all(&buf[..size])?;
+        }
+    }
+    return Ok(());
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.join().unwrap_or(());
+        }
+    }
+}
@@ -2,12 +2,21 @@ use crate::device_manager::Device;
 use crate::error::Error;
 use libssh_rs::Session;
 use r2d2::{Pool, PooledConnection};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Condvar, Mutex};
 use uuid::Uuid;
 
 pub mod connection;
+pub mod forward;
 pub mod pool;
+pub mod shell;
+
+/// Default cap on channels open at once on a single connection, so bulk operations (e.g.
+/// installing several apps) don't exhaust the server's `MaxSessions`/channel limit and trigger
+/// a `ChannelOpenFailure` storm. Callers waiting on a permit block rather than fail.
+pub(crate) const DEFAULT_MAX_CHANNELS: u32 = 4;
 
 pub struct DeviceConnection {
     id: Uuid,
@@ -15,6 +24,58 @@ pub struct DeviceConnection {
     pub user: Option<DeviceConnectionUserInfo>,
     session: Session,
     last_ok: Mutex<bool>,
+    /// Wrapped in an `Arc` so a `ChannelPermit` can outlive a move of this connection — `Proc`'s
+    /// long-lived channel acquires one before the pooled connection handle it was checked out
+    /// under gets reassigned into a longer-lived binding.
+    pub(crate) channel_gate: Arc<(Mutex<u32>, Condvar)>,
+    capabilities: Mutex<Option<Capabilities>>,
+    /// The SSH authentication banner presented during handshake, if any. webOS devices in
+    /// developer mode often put the dev-mode session's expiry in here.
+    banner: Option<String>,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    commands_executed: AtomicU64,
+    /// Bastion hop this connection was tunneled through, if `device.jump_host` was set. Only
+    /// ever read by its `Drop` impl — held here purely to keep the tunnel alive as long as
+    /// `session` needs it.
+    _jump_tunnel: Option<forward::JumpTunnel>,
+}
+
+/// Point-in-time byte/command counters from [`DeviceConnection::metrics`], for diagnosing
+/// whether a slow transfer is actually progressing.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionMetrics {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub commands_executed: u64,
+}
+
+/// Typed fields pulled out of `/var/run/nyx/device_info.json`, from
+/// [`DeviceConnection::device_info`]: a stable, stronger-typed alternative to every caller
+/// re-parsing the raw JSON itself. Every field is optional since the key it's read from has
+/// been renamed (or was never present) on at least one webOS version in the wild; absence here
+/// just means "not on this device", not a parse failure.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfo {
+    pub model_name: Option<String>,
+    pub webos_version: Option<String>,
+    pub sdk_version: Option<String>,
+    pub board_type: Option<String>,
+    pub device_arch: Option<String>,
+}
+
+/// What a device can do, probed once per connection and cached for its lifetime — these
+/// don't change while a device stays on the same webOS build, so there's no need to
+/// invalidate the cache short of reconnecting.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub webos_version: Option<String>,
+    pub ares_install: bool,
+    pub npm: bool,
+    pub luna_send: bool,
 }
 
 #[derive(Debug)]
@@ -29,6 +90,96 @@ pub struct Id {
     pub name: Option<String>,
 }
 
+/// Result of [`DeviceConnection::exec`]: the full stdout/stderr capture plus exit status.
+pub struct ExecOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: i32,
+}
+
+/// One mount's usage from [`DeviceConnection::disk_usage`], in bytes.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub mount: String,
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+/// A shareable flag for aborting an in-flight [`DeviceConnection::exec_cancellable`] from
+/// another thread. Cloning shares the same underlying flag — clone it before handing the
+/// original to the blocking `exec_cancellable` call, keeping the clone to call `cancel()` on.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<Mutex<bool>>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        return CancelToken::default();
+    }
+
+    pub fn cancel(&self) {
+        *self.0.lock().unwrap() = true;
+    }
+
+    fn is_cancelled(&self) -> bool {
+        return *self.0.lock().unwrap();
+    }
+}
+
+/// One change detected between two snapshots taken by [`DeviceConnection::poll_dir`].
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DirChange {
+    Added { path: String },
+    Removed { path: String },
+    Modified { path: String },
+}
+
+/// Signals [`DeviceConnection::kill`] can send. A fixed allow-list rather than a raw string,
+/// since the signal name is interpolated directly into a shell command — an arbitrary string
+/// from the frontend would otherwise be a command-injection vector.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Sig {
+    #[serde(rename = "TERM")]
+    Term,
+    #[serde(rename = "KILL")]
+    Kill,
+    #[serde(rename = "HUP")]
+    Hup,
+    #[serde(rename = "INT")]
+    Int,
+    #[serde(rename = "USR1")]
+    Usr1,
+    #[serde(rename = "USR2")]
+    Usr2,
+}
+
+impl Sig {
+    fn as_str(&self) -> &'static str {
+        return match self {
+            Sig::Term => "TERM",
+            Sig::Kill => "KILL",
+            Sig::Hup => "HUP",
+            Sig::Int => "INT",
+            Sig::Usr1 => "USR1",
+            Sig::Usr2 => "USR2",
+        };
+    }
+}
+
+/// One entry from [`DeviceConnection::list_processes`], parsed from `/proc/[pid]/stat` and
+/// `/proc/[pid]/cmdline` rather than `ps`, since BusyBox's `ps` (what webOS ships) doesn't
+/// reliably expose `cmdline` or RSS across builds.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: String,
+    /// Resident set size in bytes, assuming the device's usual 4096-byte page size.
+    pub rss: u64,
+}
+
 pub type ManagedDeviceConnection = PooledConnection<DeviceConnectionManager>;
 
 pub struct DeviceConnectionPool {
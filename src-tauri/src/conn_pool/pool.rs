@@ -14,10 +14,16 @@ use crate::error::Error;
 impl DeviceConnectionPool {
     pub fn new(device: Device, ssh_dir: Option<PathBuf>) -> DeviceConnectionPool {
         let last_error = Arc::<Mutex<Option<Error>>>::default();
+        // r2d2 already runs a background reaper that evicts idle connections past this TTL
+        // with a clean disconnect, and it only ever touches idle pool members — a connection
+        // currently checked out for a command is never a candidate, so there's no risk of it
+        // cutting a command off mid-flight.
+        let idle_timeout = Duration::from_secs(device.idle_timeout_secs.unwrap_or(900) as u64);
         let inner = Pool::<DeviceConnectionManager>::builder()
             .min_idle(Some(0))
             .max_size(3)
-            .idle_timeout(Some(Duration::from_secs(900)))
+            .idle_timeout(Some(idle_timeout))
+            .test_on_check_out(true)
             .error_handler(Box::new(DeviceConnectionErrorHandler {
                 last_error: last_error.clone(),
             }))
@@ -25,6 +31,12 @@ impl DeviceConnectionPool {
         return DeviceConnectionPool { inner, last_error };
     }
 
+    /// Current size/idle counts from the underlying r2d2 pool, for debugging "too many open
+    /// connections" reports.
+    pub fn state(&self) -> r2d2::State {
+        return self.inner.state();
+    }
+
     pub fn get(&self) -> Result<ManagedDeviceConnection, Error> {
         return match self.inner.get() {
             Ok(c) => {
@@ -51,7 +63,12 @@ impl ManageConnection for DeviceConnectionManager {
         return DeviceConnection::new(self.device.clone(), self.ssh_dir.as_deref());
     }
 
-    fn is_valid(&self, _: &mut Self::Connection) -> Result<(), Self::Error> {
+    /// Sends a cheap no-op exec as a keepalive/liveness probe whenever a pooled connection is
+    /// checked out. TVs silently drop idle SSH connections after a few minutes, and without
+    /// this the first real command on a stale connection would fail with a confusing
+    /// `ChannelOpenFailure` instead of just reconnecting.
+    fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        conn.exec("true", None)?;
         return Ok(());
     }
 
@@ -74,10 +91,7 @@ impl HandleError<Error> for DeviceConnectionErrorHandler {
     }
 
     fn can_retry(&self, error: &Error, num_retries: u32) -> bool {
-        if *error == Error::Disconnected {
-            return num_retries < 3;
-        }
-        return false;
+        return error.is_retryable() && num_retries < 3;
     }
 }
 
@@ -0,0 +1,83 @@
+use std::io::Write;
+use std::time::Duration;
+
+use libssh_rs::Channel;
+use rand::Rng;
+
+use crate::conn_pool::DeviceConnection;
+use crate::error::Error;
+
+impl DeviceConnection {
+    /// Opens a persistent shell channel that commands can be multiplexed through via
+    /// [`Shell::run_command`], instead of each command paying for its own channel open.
+    /// Trades per-command isolation (a crashed/stuck command wedges the whole `Shell`) for
+    /// much lower latency on rapid-fire small commands. A `Shell` is not gated by
+    /// [`DeviceConnection`]'s channel permit since it's meant to be held open and reused,
+    /// not opened per call.
+    pub fn open_shell(&self) -> Result<Shell, Error> {
+        let channel = self.new_channel()?;
+        channel.open_session()?;
+        channel.request_shell()?;
+        return Ok(Shell { channel });
+    }
+}
+
+/// Handle to a persistent shell channel opened by [`DeviceConnection::open_shell`]. Not safe
+/// to call [`Shell::run_command`] concurrently from multiple threads — output is delimited by
+/// reading until a sentinel rather than by channel framing, so overlapping commands would
+/// interleave their output.
+pub struct Shell {
+    channel: Channel,
+}
+
+impl Shell {
+    /// Writes `cmd` into the shell followed by a freshly generated sentinel echo, then reads
+    /// until that sentinel appears, parsing the exit code from the `echo $?` it carries. A
+    /// new random marker is generated per call so a previous command's output left in the
+    /// channel's buffer can never be mistaken for the current command's terminator.
+    pub fn run_command(&self, cmd: &str) -> Result<Vec<u8>, Error> {
+        let marker: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let sentinel = format!("__devman_shell_{marker}__");
+        self.channel
+            .stdin()
+            .write_all(format!("{cmd}; echo \"{sentinel} $?\"\n").as_bytes())?;
+
+        let mut buf = [0u8; 8192];
+        let mut output = Vec::new();
+        loop {
+            if self.channel.is_eof() || self.channel.is_closed() {
+                return Err(Error::Disconnected {
+                    device: None,
+                    command: Some(crate::conn_pool::connection::redact_secrets(cmd)),
+                });
+            }
+            let n = self
+                .channel
+                .read_timeout(&mut buf, false, Some(Duration::from_secs(30)))?;
+            if n == 0 {
+                continue;
+            }
+            output.extend_from_slice(&buf[..n]);
+            if let Some(pos) = find_subslice(&output, sentinel.as_bytes()) {
+                let status = String::from_utf8_lossy(&output[pos..])
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|s| s.parse::<i32>().ok())
+                    .unwrap_or(0);
+                let result = output[..pos].to_vec();
+                if status != 0 {
+                    return Err(Error::exit_status(cmd, status, Vec::new(), None));
+                }
+                return Ok(result);
+            }
+        }
+    }
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack.windows(needle.len()).position(|w| w == needle);
+}
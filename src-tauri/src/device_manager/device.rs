@@ -1,7 +1,88 @@
-use crate::device_manager::Device;
+use std::time::Duration;
+
+use crate::device_manager::{Device, DeviceProbe};
+use crate::error::Error;
+
+/// Applied when a device doesn't set `connect_timeout_ms`. Long enough for a TV waking from
+/// sleep to answer, short enough that an off/unreachable TV fails fast instead of hanging for
+/// the OS's full TCP connect timeout (which can be minutes).
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
 impl Device {
     pub(crate) fn valid_passphrase(&self) -> Option<String> {
         return self.passphrase.clone().filter(|s| !s.is_empty());
     }
+
+    pub(crate) fn connect_timeout(&self) -> Duration {
+        return self
+            .connect_timeout_ms
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+    }
+
+    /// Parses `host` into a literal/hostname libssh can resolve, plus an optional port if one
+    /// was embedded in the field. Accepts bare hostnames and IPv4 literals as-is, and bracketed
+    /// IPv6 literals (`[::1]`, `[fe80::1%eth0]`, optionally followed by `:port`) the way a user
+    /// might paste a URL authority. The zone identifier on a link-local literal is passed
+    /// through unmodified — getaddrinfo resolves `%eth0` to an interface index itself. Returns
+    /// `Error::InvalidHost` for malformed brackets rather than letting it fall through to a
+    /// confusing DNS resolution failure later.
+    pub(crate) fn parse_host(host: &str) -> Result<(String, Option<u16>), Error> {
+        if let Some(rest) = host.strip_prefix('[') {
+            let Some(end) = rest.find(']') else {
+                return Err(Error::InvalidHost {
+                    host: host.to_string(),
+                });
+            };
+            let addr = &rest[..end];
+            if addr.is_empty() {
+                return Err(Error::InvalidHost {
+                    host: host.to_string(),
+                });
+            }
+            let after = &rest[end + 1..];
+            let port = if after.is_empty() {
+                None
+            } else if let Some(p) = after.strip_prefix(':') {
+                Some(p.parse::<u16>().map_err(|_| Error::InvalidHost {
+                    host: host.to_string(),
+                })?)
+            } else {
+                return Err(Error::InvalidHost {
+                    host: host.to_string(),
+                });
+            };
+            return Ok((addr.to_string(), port));
+        }
+        if host.is_empty() {
+            return Err(Error::InvalidHost {
+                host: host.to_string(),
+            });
+        }
+        return Ok((host.to_string(), None));
+    }
+}
+
+impl DeviceProbe {
+    /// Best-effort parse of `/var/run/nyx/device_info.json`, falling back to an empty
+    /// `DeviceProbe` when the device doesn't have nyx (e.g. it only replied with `uname -a`).
+    pub(crate) fn parse(stdout: &[u8]) -> DeviceProbe {
+        return serde_json::from_slice::<serde_json::Value>(stdout)
+            .ok()
+            .map(|v| DeviceProbe {
+                model_name: v
+                    .get("model_name")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                platform_version: v
+                    .get("webos_build_id")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                device_arch: v
+                    .get("device_arch")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            })
+            .unwrap_or_default();
+    }
 }
@@ -1,16 +1,35 @@
+use std::collections::HashSet;
 use std::fs;
+use std::net::Ipv4Addr;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use libssh_rs::{Session, SshKey};
+use regex::Regex;
 use tokio::fs::{remove_file, File};
 use tokio::io::AsyncWriteExt;
+use tokio::net::UdpSocket;
 
 use crate::app_dirs::{GetConfDir, GetSshDir, SetConfDir, SetSshDir};
 use crate::conn_pool::DeviceConnection;
 use crate::device_manager::io::{read, write};
-use crate::device_manager::{Device, DeviceCheckConnection, DeviceManager, PrivateKey};
+use crate::device_manager::{
+    Device, DeviceCheckConnection, DeviceManager, DevicePatch, DeviceProbe, DiscoveredDevice,
+    ImportPreview, JumpHostConfig, PrivateKey,
+};
 use crate::error::Error;
 
+/// The handful of `~/.ssh/config` keys [`DeviceManager::parse_ssh_config`] collects for a
+/// matching `Host` block.
+#[derive(Default, Debug, PartialEq)]
+struct SshConfigEntry {
+    host_name: Option<String>,
+    port: Option<u16>,
+    user: Option<String>,
+    identity_file: Option<String>,
+    proxy_jump: Option<String>,
+}
+
 impl DeviceManager {
     pub async fn list(&self) -> Result<Vec<Device>, Error> {
         let devices = read(&self.ensure_conf_dir()?).await?;
@@ -18,25 +37,187 @@ impl DeviceManager {
         return Ok(devices);
     }
 
-    pub async fn set_default(&self, name: &str) -> Result<Option<Device>, Error> {
+    pub async fn get(&self, name: &str) -> Result<Option<Device>, Error> {
+        let devices = read(&self.ensure_conf_dir()?).await?;
+        return Ok(devices.into_iter().find(|d| d.name == name));
+    }
+
+    /// Serializes the device list as JSON, for backup or transfer to another install. Strips
+    /// `password`/`passphrase` unless `include_secrets` is set, since the common case is
+    /// sharing a device list around without also handing out credentials.
+    pub async fn export(&self, include_secrets: bool) -> Result<String, Error> {
+        let mut devices = read(&self.ensure_conf_dir()?).await?;
+        if !include_secrets {
+            for device in devices.iter_mut() {
+                device.password = None;
+                device.passphrase = None;
+            }
+        }
+        return Ok(serde_json::to_string_pretty(&devices)?);
+    }
+
+    /// Previews what [`DeviceManager::import`] would change for the same `json`/`merge`,
+    /// without writing anything — reads the same persisted device list the real import reads,
+    /// so a confirmation dialog shown right before calling `import` can't disagree with what
+    /// actually happens.
+    pub async fn import_preview(&self, json: &str, merge: bool) -> Result<ImportPreview, Error> {
+        let imported: Vec<Device> = serde_json::from_str(json)?;
+        let existing = read(&self.ensure_conf_dir()?).await?;
+        let existing_names: HashSet<String> = existing.iter().map(|d| d.name.clone()).collect();
+        if merge {
+            let (skipped, added): (Vec<Device>, Vec<Device>) = imported
+                .into_iter()
+                .partition(|d| existing_names.contains(&d.name));
+            return Ok(ImportPreview {
+                added,
+                overwritten: Vec::new(),
+                skipped,
+                removed: Vec::new(),
+            });
+        }
+        let imported_names: HashSet<String> = imported.iter().map(|d| d.name.clone()).collect();
+        let (overwritten, added): (Vec<Device>, Vec<Device>) = imported
+            .into_iter()
+            .partition(|d| existing_names.contains(&d.name));
+        let removed: Vec<Device> = existing
+            .into_iter()
+            .filter(|d| !imported_names.contains(&d.name))
+            .collect();
+        return Ok(ImportPreview {
+            added,
+            overwritten,
+            skipped: Vec::new(),
+            removed,
+        });
+    }
+
+    /// Imports a device list previously produced by [`DeviceManager::export`]. With `merge`,
+    /// an imported device whose name already exists is skipped rather than overwriting it or
+    /// erroring, mirroring [`DeviceManager::add`]'s own name-uniqueness rule; without it, the
+    /// imported list replaces the current one outright.
+    pub async fn import(&self, json: &str, merge: bool) -> Result<Vec<Device>, Error> {
+        let imported: Vec<Device> = serde_json::from_str(json)?;
+        let conf_dir = self.ensure_conf_dir()?;
+        let devices = if merge {
+            let mut existing = read(&conf_dir).await?;
+            let existing_names: HashSet<String> =
+                existing.iter().map(|d| d.name.clone()).collect();
+            for device in imported {
+                if !existing_names.contains(&device.name) {
+                    existing.push(device);
+                }
+            }
+            existing
+        } else {
+            imported
+        };
+        write(devices.clone(), &conf_dir).await?;
+        return Ok(devices);
+    }
+
+    /// Records `fingerprint` as the trusted host key for `name`, so future connections are
+    /// verified against it instead of trusting whatever key the device presents.
+    pub async fn trust_host_key(&self, name: &str, fingerprint: &str) -> Result<(), Error> {
+        let conf_dir = self.ensure_conf_dir()?;
+        let mut devices = read(&conf_dir).await?;
+        let device = devices
+            .iter_mut()
+            .find(|d| d.name == name)
+            .ok_or(Error::NotFound)?;
+        device.host_key_fingerprint = Some(String::from(fingerprint));
+        write(devices, &conf_dir).await?;
+        return Ok(());
+    }
+
+    /// Renames a device in place, preserving its default flag, trusted host key and any other
+    /// persisted state. Errors if `old` doesn't exist or `new` is already taken. Callers should
+    /// also evict `old`'s pooled connection (see `SessionManager::disconnect`), since a pool is
+    /// keyed by device name.
+    pub async fn rename(&self, old: &str, new: &str) -> Result<(), Error> {
+        let conf_dir = self.ensure_conf_dir()?;
+        let mut devices = read(&conf_dir).await?;
+        if devices.iter().any(|d| d.name == new) {
+            return Err(Error::io(std::io::ErrorKind::AlreadyExists));
+        }
+        let device = devices
+            .iter_mut()
+            .find(|d| d.name == old)
+            .ok_or(Error::NotFound)?;
+        device.name = String::from(new);
+        write(devices, &conf_dir).await?;
+        return Ok(());
+    }
+
+    /// Applies `patch` to the device named `name` in place, overwriting only the fields that
+    /// are set. Useful when a TV's DHCP lease changes its IP and the user just wants to fix
+    /// up the host, rather than delete and recreate the device. Callers should evict any
+    /// existing pooled connection for `name` afterwards (see `SessionManager::disconnect`).
+    pub async fn update(&self, name: &str, patch: DevicePatch) -> Result<Device, Error> {
+        let conf_dir = self.ensure_conf_dir()?;
+        let mut devices = read(&conf_dir).await?;
+        let device = devices
+            .iter_mut()
+            .find(|d| d.name == name)
+            .ok_or(Error::NotFound)?;
+        if let Some(host) = patch.host {
+            device.host = host;
+        }
+        if let Some(port) = patch.port {
+            device.port = port;
+        }
+        if let Some(username) = patch.username {
+            device.username = username;
+        }
+        if patch.password.is_some() {
+            device.password = patch.password;
+        }
+        if patch.private_key.is_some() {
+            device.private_key = patch.private_key;
+        }
+        let updated = device.clone();
+        write(devices, &conf_dir).await?;
+        return Ok(updated);
+    }
+
+    /// Marks the device named by `name` as default, clearing the flag on every other device
+    /// so at most one is ever marked default. Pass `None` to unset the default entirely,
+    /// returning whichever device previously held it.
+    pub async fn set_default(&self, name: Option<&str>) -> Result<Option<Device>, Error> {
         let conf_dir = self.ensure_conf_dir()?;
         let mut devices = read(&conf_dir).await?;
         let mut result: Option<Device> = None;
         for device in &mut devices {
-            if device.name == name {
+            if name.is_some() && Some(device.name.as_str()) == name {
                 device.default = Some(true);
-                result = Some(device.clone());
             } else {
+                if name.is_none() && device.default.unwrap_or(false) {
+                    result = Some(device.clone());
+                }
                 device.default = None;
             }
         }
+        if let Some(name) = name {
+            result = devices.iter().find(|d| d.name == name).cloned();
+        }
         log::trace!("{:?}", devices);
         write(devices, &conf_dir).await?;
         return Ok(result);
     }
 
+    /// Unsets whichever device is currently marked default. Equivalent to
+    /// `set_default(None)`.
+    pub async fn clear_default(&self) -> Result<Option<Device>, Error> {
+        return self.set_default(None).await;
+    }
+
     pub async fn add(&self, device: &Device) -> Result<Device, Error> {
+        if device.name.is_empty() {
+            return Err(Error::new("Device name must not be empty"));
+        }
         let conf_dir = self.ensure_conf_dir()?;
+        if read(&conf_dir).await?.iter().any(|d| d.name == device.name) {
+            return Err(Error::io(std::io::ErrorKind::AlreadyExists));
+        }
         let mut device = device.clone();
         if let Some(key) = &device.private_key {
             match key {
@@ -67,6 +248,16 @@ impl DeviceManager {
         return Ok(device);
     }
 
+    /// Previews what [`DeviceManager::remove`] would delete for the same `name`, without
+    /// touching the store or the pool — reads the same persisted list the real removal reads.
+    pub async fn remove_preview(&self, name: &str) -> Result<Vec<Device>, Error> {
+        let devices = read(&self.ensure_conf_dir()?).await?;
+        return Ok(devices.into_iter().filter(|d| d.name == name).collect());
+    }
+
+    /// Idempotent: removing a `name` that isn't in the list just rewrites the list unchanged
+    /// and returns `Ok(())`, rather than erroring, so a frontend double-click or retried undo
+    /// isn't a failure case callers need to special-case.
     pub async fn remove(&self, name: &str, remove_key: bool) -> Result<(), Error> {
         let conf_dir = self.ensure_conf_dir()?;
         let devices = read(&conf_dir).await?;
@@ -97,6 +288,124 @@ impl DeviceManager {
         return Ok(());
     }
 
+    /// Builds a `Device` from the user's `~/.ssh/config`, resolving `alias` against its `Host`
+    /// blocks the way OpenSSH itself does: the first matching block to set a given key wins,
+    /// later matching blocks only fill in keys still unset. Supports the handful of keys that
+    /// matter for connecting (`HostName`, `Port`, `User`, `IdentityFile`, `ProxyJump`); anything
+    /// else in the file is ignored. Errors with [`Error::NotFound`] if no `Host` block matches
+    /// `alias` at all, so a caller can fall back to asking the user for connection details.
+    pub fn from_ssh_config(&self, alias: &str) -> Result<Device, Error> {
+        let config_path = self.ensure_ssh_dir()?.join("config");
+        let content = fs::read_to_string(&config_path)?;
+        let entry = Self::parse_ssh_config(&content, alias).ok_or(Error::NotFound)?;
+        return Ok(Device {
+            order: None,
+            default: None,
+            profile: String::from("ose"),
+            name: String::from(alias),
+            description: None,
+            host: entry.host_name.unwrap_or_else(|| String::from(alias)),
+            port: entry.port.unwrap_or(22),
+            username: entry.user.unwrap_or_else(|| String::from("root")),
+            new: true,
+            private_key: entry.identity_file.map(|name| PrivateKey::Path { name }),
+            files: None,
+            passphrase: None,
+            password: None,
+            log_daemon: None,
+            no_port_forwarding: None,
+            indelible: None,
+            host_key_fingerprint: None,
+            use_agent: None,
+            connect_timeout_ms: None,
+            compression: None,
+            idle_timeout_secs: None,
+            max_channels: None,
+            shared_connection: None,
+            jump_host: entry.proxy_jump.map(|jump| Self::proxy_jump_config(&jump)),
+        });
+    }
+
+    /// Resolves `alias` against `content` (an `~/.ssh/config`-formatted string) the way OpenSSH
+    /// itself does: the first matching `Host` block to set a given key wins, later matching
+    /// blocks only fill in keys still unset. Returns `None` if no `Host` block matches `alias`
+    /// at all. Pulled out of [`Self::from_ssh_config`] so the parsing logic can be tested
+    /// against a string directly, without touching the filesystem.
+    fn parse_ssh_config(content: &str, alias: &str) -> Option<SshConfigEntry> {
+        let mut entry = SshConfigEntry::default();
+        let mut matched = false;
+        let mut matching = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            if key.eq_ignore_ascii_case("Host") {
+                matching = value
+                    .split_whitespace()
+                    .any(|pattern| Self::ssh_config_pattern_matches(pattern, alias));
+                matched |= matching;
+            } else if matching && key.eq_ignore_ascii_case("HostName") && entry.host_name.is_none() {
+                entry.host_name = Some(value.to_string());
+            } else if matching && key.eq_ignore_ascii_case("Port") && entry.port.is_none() {
+                entry.port = value.parse().ok();
+            } else if matching && key.eq_ignore_ascii_case("User") && entry.user.is_none() {
+                entry.user = Some(value.to_string());
+            } else if matching
+                && key.eq_ignore_ascii_case("IdentityFile")
+                && entry.identity_file.is_none()
+            {
+                entry.identity_file = Some(value.to_string());
+            } else if matching && key.eq_ignore_ascii_case("ProxyJump") && entry.proxy_jump.is_none()
+            {
+                entry.proxy_jump = Some(value.to_string());
+            }
+        }
+        if !matched {
+            return None;
+        }
+        return Some(entry);
+    }
+
+    /// Matches one whitespace-separated token of an ssh config `Host` line against `alias`,
+    /// supporting the `*`/`?` globs OpenSSH itself allows there. Good enough for the common
+    /// case of a literal alias or a `*` catch-all default block; doesn't attempt `!negation`.
+    fn ssh_config_pattern_matches(pattern: &str, alias: &str) -> bool {
+        let regex_source = format!(
+            "^{}$",
+            regex::escape(pattern)
+                .replace(r"\*", ".*")
+                .replace(r"\?", ".")
+        );
+        return Regex::new(&regex_source)
+            .map(|re| re.is_match(alias))
+            .unwrap_or(false);
+    }
+
+    /// Turns a bare `ProxyJump` value (`[user@]host[:port]`) into a `JumpHostConfig`. Doesn't
+    /// resolve it against another `Host` alias in the config — ssh config's `ProxyJump` can name
+    /// either, but chasing an alias through the same file's auth/identity settings is more than
+    /// this needs; a jump host specified that way can still be added manually afterwards.
+    fn proxy_jump_config(value: &str) -> JumpHostConfig {
+        let (user, host_port) = match value.split_once('@') {
+            Some((user, rest)) => (String::from(user), rest),
+            None => (String::from("root"), value),
+        };
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => (String::from(host), port.parse().unwrap_or(22)),
+            None => (String::from(host_port), 22),
+        };
+        return JumpHostConfig {
+            host,
+            port,
+            username: user,
+            private_key: None,
+            password: None,
+            passphrase: None,
+        };
+    }
+
     //noinspection HttpUrlsUsage
     pub async fn novacom_getkey(&self, address: &str, passphrase: &str) -> Result<String, Error> {
         let resp = reqwest::get(format!("http://{address}:9991/webos_rsa"))
@@ -131,6 +440,26 @@ impl DeviceManager {
         };
     }
 
+    /// Opens a throwaway SSH connection (never added to any pool) to sanity-check a device's
+    /// host/port/credentials before it's saved, and returns what little device info we can
+    /// glean from it.
+    pub async fn test_connection(&self, device: &Device) -> Result<DeviceProbe, Error> {
+        let device = device.clone();
+        let ssh_dir = self.get_ssh_dir();
+        return tokio::task::spawn_blocking(move || {
+            let conn = DeviceConnection::new(device, ssh_dir.as_deref())?;
+            let output = conn.exec(
+                "cat /var/run/nyx/device_info.json 2>/dev/null || uname -a",
+                None,
+            )?;
+            let mut probe = DeviceProbe::parse(&output.stdout);
+            probe.banner = conn.banner().map(String::from);
+            return Ok(probe);
+        })
+        .await
+        .expect("critical failure in device_manager::test_connection task");
+    }
+
     pub async fn check_connection(&self, host: &str) -> Result<DeviceCheckConnection, Error> {
         async fn ssh_probe(host: &str, port: u16, user: &str) -> Result<String, Error> {
             let host = host.to_string();
@@ -162,6 +491,61 @@ impl DeviceManager {
             key_server: key_server_probe(host).await.is_ok(),
         });
     }
+
+    /// Broadcasts an SSDP M-SEARCH for webOS's second-screen service type and collects
+    /// responders for `timeout`, deduped by IP. Doesn't touch the saved device list — the
+    /// frontend offers candidates to the user, who registers one via `add` if they want it.
+    pub async fn discover(&self, timeout: Duration) -> Result<Vec<DiscoveredDevice>, Error> {
+        const MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+        const MULTICAST_PORT: u16 = 1900;
+        const SERVICE_TYPE: &str = "urn:lge-com:service:webos-second-screen:1";
+
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        let request = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {MULTICAST_ADDR}:{MULTICAST_PORT}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {SERVICE_TYPE}\r\n\r\n"
+        );
+        socket
+            .send_to(request.as_bytes(), (MULTICAST_ADDR, MULTICAST_PORT))
+            .await?;
+
+        let mut found = Vec::<DiscoveredDevice>::new();
+        let mut seen = HashSet::<String>::new();
+        let mut buf = [0u8; 2048];
+        let started = std::time::Instant::now();
+        while let Some(remaining) = timeout.checked_sub(started.elapsed()) {
+            if remaining.is_zero() {
+                break;
+            }
+            let Ok(Ok((len, addr))) = tokio::time::timeout(remaining, socket.recv_from(&mut buf)).await else {
+                break;
+            };
+            let host = addr.ip().to_string();
+            if seen.contains(&host) {
+                continue;
+            }
+            if let Some(name) = Self::parse_ssdp_name(&buf[..len]) {
+                seen.insert(host.clone());
+                found.push(DiscoveredDevice { name, host });
+            }
+        }
+        return Ok(found);
+    }
+
+    /// Best-effort friendly name from an M-SEARCH response's `USN` header. A proper UPnP
+    /// friendly name lives in the XML document at the `LOCATION` header, but fetching and
+    /// parsing that is more than this needs just to list candidates.
+    fn parse_ssdp_name(data: &[u8]) -> Option<String> {
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut response = httparse::Response::new(&mut headers);
+        if response.parse(data).is_err() || response.code != Some(200) {
+            return None;
+        }
+        return response
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("USN"))
+            .map(|h| String::from_utf8_lossy(h.value).to_string());
+    }
 }
 
 impl GetSshDir for DeviceManager {
@@ -187,3 +571,80 @@ impl SetConfDir for DeviceManager {
         *self.conf_dir.lock().unwrap() = Some(dir);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ssh_config_matches_first_block() {
+        let content = "\
+Host tv1 tv2
+    HostName 192.168.1.10
+    Port 2222
+    User prisoner
+    IdentityFile ~/.ssh/tv_rsa
+
+Host *
+    User root
+";
+        let entry = DeviceManager::parse_ssh_config(content, "tv2").unwrap();
+        assert_eq!(entry.host_name.as_deref(), Some("192.168.1.10"));
+        assert_eq!(entry.port, Some(2222));
+        assert_eq!(entry.user.as_deref(), Some("prisoner"));
+        assert_eq!(entry.identity_file.as_deref(), Some("~/.ssh/tv_rsa"));
+    }
+
+    #[test]
+    fn parse_ssh_config_first_matching_block_wins() {
+        let content = "\
+Host tv*
+    User first
+
+Host tv1
+    User second
+";
+        let entry = DeviceManager::parse_ssh_config(content, "tv1").unwrap();
+        assert_eq!(entry.user.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn parse_ssh_config_no_match_returns_none() {
+        let content = "Host other\n    User root\n";
+        assert!(DeviceManager::parse_ssh_config(content, "tv1").is_none());
+    }
+
+    #[test]
+    fn parse_ssh_config_ignores_comments_and_quoting() {
+        let content = "\
+# a comment
+Host tv1
+    HostName \"192.168.1.10\" # trailing comment
+";
+        let entry = DeviceManager::parse_ssh_config(content, "tv1").unwrap();
+        assert_eq!(entry.host_name.as_deref(), Some("192.168.1.10"));
+    }
+
+    #[test]
+    fn ssh_config_pattern_matches_glob() {
+        assert!(DeviceManager::ssh_config_pattern_matches("tv*", "tv1"));
+        assert!(DeviceManager::ssh_config_pattern_matches("*", "anything"));
+        assert!(!DeviceManager::ssh_config_pattern_matches("tv1", "tv2"));
+    }
+
+    #[test]
+    fn proxy_jump_config_parses_user_host_port() {
+        let config = DeviceManager::proxy_jump_config("bastion@10.0.0.1:2222");
+        assert_eq!(config.username, "bastion");
+        assert_eq!(config.host, "10.0.0.1");
+        assert_eq!(config.port, 2222);
+    }
+
+    #[test]
+    fn proxy_jump_config_defaults_user_and_port() {
+        let config = DeviceManager::proxy_jump_config("10.0.0.1");
+        assert_eq!(config.username, "root");
+        assert_eq!(config.host, "10.0.0.1");
+        assert_eq!(config.port, 22);
+    }
+}
@@ -67,6 +67,72 @@ pub struct Device {
     pub no_port_forwarding: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub indelible: Option<bool>,
+    #[serde(rename = "hostKeyFingerprint", skip_serializing_if = "Option::is_none")]
+    pub host_key_fingerprint: Option<String>,
+    #[serde(rename = "useAgent", default, skip_serializing_if = "Option::is_none")]
+    pub use_agent: Option<bool>,
+    #[serde(
+        rename = "connectTimeoutMs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub connect_timeout_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<bool>,
+    #[serde(
+        rename = "idleTimeoutSecs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idle_timeout_secs: Option<u32>,
+    /// Caps how many exec-family SSH channels (every `exec*` call and every spawned `Proc`) this
+    /// device's `DeviceConnection` will have open at once; callers past the cap block until one
+    /// frees up. SFTP sessions and shell channels are not counted against it. Defaults to
+    /// `conn_pool::DEFAULT_MAX_CHANNELS`, which is generous enough for normal use — this exists
+    /// for devices whose webOS build chokes on too many concurrent channels.
+    ///
+    /// This and the handful of other per-connection tunables above (`compression`,
+    /// `connectTimeoutMs`, `idleTimeoutSecs`) were deliberately kept as individual optional
+    /// fields on `Device` rather than bundled into a nested config struct: they're already
+    /// independently optional with independent defaults, and a nested struct would mean every
+    /// existing persisted device gains a wrapper object on next save for no behavioral gain.
+    #[serde(
+        rename = "maxChannels",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_channels: Option<u32>,
+    /// When set, `SessionManager` pools this device's connection under its `host:port:username`
+    /// rather than its name, so two device entries pointing at the same credentials on the same
+    /// host share one pooled connection instead of opening a second. Off by default, since most
+    /// devices are registered once under one name.
+    #[serde(
+        rename = "sharedConnection",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shared_connection: Option<bool>,
+    /// When set, `DeviceConnection` reaches this device by first connecting to the bastion
+    /// described here, then tunneling the real SSH session through a forwarded channel on that
+    /// connection, for TVs that only live on a network segment reachable through a jump host.
+    #[serde(rename = "jumpHost", default, skip_serializing_if = "Option::is_none")]
+    pub jump_host: Option<JumpHostConfig>,
+}
+
+/// Bastion to tunnel a [`Device`] connection through. Deliberately a narrower shape than
+/// `Device` itself — no host key pinning, compression/channel tuning, etc. — since those are
+/// properties of the target connection, not the bastion hop.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JumpHostConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    #[serde(rename = "privateKey", skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<PrivateKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -84,3 +150,45 @@ pub struct DeviceCheckConnection {
     pub ssh_9922: Option<String>,
     pub key_server: bool,
 }
+
+/// Partial update for [`DeviceManager::update`]: only fields set to `Some` are applied.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePatch {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub private_key: Option<PrivateKey>,
+}
+
+/// Preview of what `DeviceManager::import` would change, without writing anything — for a
+/// confirm dialog that needs to show exact consequences before the user commits.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub added: Vec<Device>,
+    pub overwritten: Vec<Device>,
+    pub skipped: Vec<Device>,
+    pub removed: Vec<Device>,
+}
+
+/// A device found by [`DeviceManager::discover`]. Not yet a registered [`Device`] — the
+/// frontend offers it to the user, who registers it via `add` if they want it.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredDevice {
+    pub name: String,
+    pub host: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProbe {
+    pub model_name: Option<String>,
+    pub platform_version: Option<String>,
+    pub device_arch: Option<String>,
+    /// The SSH banner captured while connecting, if the device sent one — see
+    /// [`crate::conn_pool::DeviceConnection::banner`].
+    pub banner: Option<String>,
+}
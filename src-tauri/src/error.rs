@@ -14,17 +14,66 @@ pub enum Error {
     Authorization {
         message: String,
     },
+    /// The device rejected every auth method this connection actually attempted (e.g. a
+    /// wrong password), as opposed to [`Error::AuthMethodsExhausted`] where we had nothing
+    /// left to try.
+    AuthFailed {
+        methods_tried: Vec<String>,
+    },
+    /// Nothing beyond bare `none` auth was configured to try, and the server rejected that —
+    /// e.g. a device with no private key, no agent and no password set.
+    AuthMethodsExhausted,
     BadPassphrase,
+    /// Raised by `DeviceConnection::exec_cancellable` when its `CancelToken` was tripped
+    /// before the command finished.
+    Cancelled,
+    /// `command` didn't match `SessionManager`'s configured allow-list. Raised before any
+    /// channel is opened.
+    CommandBlocked {
+        command: String,
+    },
     BadPrivateKey {
         message: String,
     },
-    Disconnected,
+    /// `device`/`command` are `None` where the disconnect is detected without either in scope
+    /// (e.g. deep inside a `libssh_rs` error conversion) and filled in by
+    /// [`Error::with_disconnect_context`] once a caller that knows them catches it — so the
+    /// discriminant a retry loop matches on (`Error::Disconnected { .. }`) never changes, only
+    /// the detail available for logs/UI does.
+    Disconnected {
+        device: Option<String>,
+        command: Option<String>,
+    },
+    DevModeLikelyOff,
+    /// Neither already-root nor passwordless `sudo` was available to
+    /// `DeviceConnection::exec_elevated`.
+    ElevationUnavailable,
+    HostKeyChanged {
+        fingerprint: String,
+    },
+    /// `Device::host` couldn't be parsed as a hostname or IPv4/bracketed-IPv6 literal, e.g.
+    /// unbalanced brackets or a non-numeric trailing port. Raised before any connection attempt
+    /// so it doesn't masquerade as a DNS resolution failure.
+    InvalidHost {
+        host: String,
+    },
+    UnknownHostKey {
+        fingerprint: String,
+    },
+    ExitSignal {
+        signal: String,
+        core_dumped: bool,
+    },
     ExitStatus {
         message: String,
         command: String,
         exit_code: i32,
         stderr: Vec<u8>,
         unhandled: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        connection_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        index: Option<usize>,
     },
     IO {
         #[serde(serialize_with = "as_debug_string")]
@@ -37,9 +86,51 @@ pub enum Error {
         unhandled: bool,
     },
     PassphraseRequired,
+    JsonParse {
+        raw: String,
+    },
     NotFound,
+    PartialFailure {
+        paths: Vec<String>,
+    },
+    OutputTooLarge {
+        limit: usize,
+    },
     Timeout,
     Unsupported,
+    /// Raised by `DeviceConnection::upload_verified` when the checksum computed on the device
+    /// after upload doesn't match the one computed locally before it.
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// An `ares-install`-style webOS command line tool failed in a way
+    /// [`crate::conn_pool::DeviceConnection::exec_installer`] recognized from its stderr, e.g.
+    /// `FAILED_REMOVE` or `INVALID_PACKAGE`.
+    WebosTool {
+        code: String,
+        detail: String,
+    },
+    /// Raised while establishing a connection through `Device::jump_host`. `hop` is either
+    /// `"bastion"` or `"target"`, so the UI/logs can say which leg of the tunnel actually failed
+    /// instead of a bare connection error that looks identical for either.
+    JumpHostFailed {
+        hop: String,
+        message: String,
+    },
+    /// Raised by `DeviceConnection::exec_deadline`/`Proc::wait_close_deadline` when the total
+    /// wall-clock `deadline` passed, as opposed to [`Error::Timeout`]'s inactivity-gap sense —
+    /// the remote process was still producing output right up until it was killed.
+    DeadlineExceeded,
+    /// `SessionManager::max_connections` was reached and every pooled connection across every
+    /// device is currently in use, so there was nothing idle left to evict to make room.
+    PoolFull,
+    /// `DeviceConnection::luna_send`'s reply had `returnValue: false`, the luna bus's own
+    /// convention for a failed call.
+    LunaCallFailed {
+        uri: String,
+        error_text: String,
+    },
 }
 
 impl Error {
@@ -62,13 +153,143 @@ impl Error {
             unhandled: false,
         };
     }
+
+    /// Every exec-family method raises the same shape of error for a non-zero exit: `message`
+    /// is never populated (the frontend reads `exit_code`/`stderr` instead), so this fills it in
+    /// once instead of every call site repeating `message: format!("")`. `index` (which batch
+    /// element failed, for `exec_batch`) defaults to `None`; chain [`Self::with_index`] when it's
+    /// known.
+    pub fn exit_status(
+        command: impl Into<String>,
+        exit_code: i32,
+        stderr: Vec<u8>,
+        connection_id: Option<String>,
+    ) -> Error {
+        return Error::ExitStatus {
+            message: String::new(),
+            command: command.into(),
+            exit_code,
+            stderr,
+            unhandled: true,
+            connection_id,
+            index: None,
+        };
+    }
+
+    /// Attaches `exec_batch`'s failing element index to an [`Error::ExitStatus`] built by
+    /// [`Self::exit_status`]. A no-op on every other variant.
+    pub fn with_index(self, index: usize) -> Error {
+        return match self {
+            Error::ExitStatus {
+                message,
+                command,
+                exit_code,
+                stderr,
+                unhandled,
+                connection_id,
+                ..
+            } => Error::ExitStatus {
+                message,
+                command,
+                exit_code,
+                stderr,
+                unhandled,
+                connection_id,
+                index: Some(index),
+            },
+            other => other,
+        };
+    }
+
+    /// A stable, SCREAMING_SNAKE_CASE identifier for this variant, for log lines and bug
+    /// reports. This is deliberately not wired into the serialized error body: `reason` (the
+    /// `#[serde(tag = "reason")]` discriminant, e.g. `"ExitStatus"`) is already the
+    /// machine-readable field the frontend switches on, and `IO`'s own `code` field already
+    /// carries `std::io::ErrorKind` — reusing the name here for a different value would shadow
+    /// that field rather than extend it.
+    pub fn code(&self) -> &'static str {
+        return match self {
+            Error::Authorization { .. } => "AUTHORIZATION",
+            Error::AuthFailed { .. } => "AUTH_FAILED",
+            Error::AuthMethodsExhausted => "AUTH_METHODS_EXHAUSTED",
+            Error::BadPassphrase => "BAD_PASSPHRASE",
+            Error::Cancelled => "CANCELLED",
+            Error::CommandBlocked { .. } => "COMMAND_BLOCKED",
+            Error::BadPrivateKey { .. } => "BAD_PRIVATE_KEY",
+            Error::Disconnected { .. } => "DISCONNECTED",
+            Error::DevModeLikelyOff => "DEV_MODE_LIKELY_OFF",
+            Error::ElevationUnavailable => "ELEVATION_UNAVAILABLE",
+            Error::HostKeyChanged { .. } => "HOST_KEY_CHANGED",
+            Error::InvalidHost { .. } => "INVALID_HOST",
+            Error::UnknownHostKey { .. } => "UNKNOWN_HOST_KEY",
+            Error::ExitSignal { .. } => "EXIT_SIGNAL",
+            Error::ExitStatus { .. } => "EXIT_STATUS",
+            Error::IO { .. } => "IO",
+            Error::Message { .. } => "MESSAGE",
+            Error::PassphraseRequired => "PASSPHRASE_REQUIRED",
+            Error::JsonParse { .. } => "JSON_PARSE",
+            Error::NotFound => "NOT_FOUND",
+            Error::PartialFailure { .. } => "PARTIAL_FAILURE",
+            Error::OutputTooLarge { .. } => "OUTPUT_TOO_LARGE",
+            Error::Timeout => "TIMEOUT",
+            Error::Unsupported => "UNSUPPORTED",
+            Error::WebosTool { .. } => "WEBOS_TOOL",
+            Error::ChecksumMismatch { .. } => "CHECKSUM_MISMATCH",
+            Error::JumpHostFailed { .. } => "JUMP_HOST_FAILED",
+            Error::DeadlineExceeded => "DEADLINE_EXCEEDED",
+            Error::PoolFull => "POOL_FULL",
+            Error::LunaCallFailed { .. } => "LUNA_CALL_FAILED",
+        };
+    }
+
+    /// Whether a retry loop should consider this error transient and worth trying again,
+    /// rather than one that will just fail the same way a second time. Transport-level hiccups
+    /// (a dropped session, a stalled channel, a slow device) are retryable; anything the device
+    /// actively rejected (bad credentials, a blocked command, a non-zero exit) isn't — retrying
+    /// those just wastes time and, for auth failures, risks tripping a lockout. Callers like
+    /// [`crate::session_manager::SessionManager::with_session`] should consult this instead of
+    /// hardcoding which variants to retry on.
+    pub fn is_retryable(&self) -> bool {
+        return match self {
+            Error::Disconnected { .. } => true,
+            Error::Timeout => true,
+            Error::IO { code, .. } => matches!(
+                code,
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::TimedOut
+                    | ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+            ),
+            Error::PoolFull => true,
+            _ => false,
+        };
+    }
+
+    /// Fills in `Disconnected`'s `device`/`command` once both are known, for a caller like
+    /// `plugins::cmd::exec` that sits above the `?`-propagated conversions where a disconnect
+    /// is actually detected. A no-op on every other variant, so it's safe to call on any
+    /// `Result<T, Error>` via `.map_err(|e| e.with_disconnect_context(...))` without first
+    /// checking which variant it is. `command` is redacted the same way `exec`'s debug log
+    /// already is, so a command embedding a password never ends up in a bug report either way.
+    pub fn with_disconnect_context(self, device: &str, command: &str) -> Error {
+        return match self {
+            Error::Disconnected { .. } => Error::Disconnected {
+                device: Some(device.to_string()),
+                command: Some(crate::conn_pool::connection::redact_secrets(command)),
+            },
+            other => other,
+        };
+    }
 }
 
 impl ErrorTrait for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        return f.write_fmt(format_args!("AppError: {:?}", self));
+        return f.write_fmt(format_args!("AppError[{}]: {:?}", self.code(), self));
     }
 }
 
@@ -150,7 +371,7 @@ impl From<SshError> for Error {
             SshError::Fatal(s) => {
                 if let Some(socket_error) = s.strip_prefix("Socket error:") {
                     return if socket_error.trim() == "disconnected" {
-                        Error::Disconnected
+                        Error::Disconnected { device: None, command: None }
                     } else {
                         Error::IO {
                             code: ErrorKind::Other,
@@ -209,8 +430,8 @@ fn from_sftp_error_code(code: u32, message: String) -> Error {
         libssh_rs_sys::SSH_FX_NO_SUCH_FILE => Error::io(ErrorKind::NotFound),
         libssh_rs_sys::SSH_FX_PERMISSION_DENIED => Error::io(ErrorKind::PermissionDenied),
         libssh_rs_sys::SSH_FX_FAILURE => Error::new("Failed to perform this operation"),
-        libssh_rs_sys::SSH_FX_NO_CONNECTION => Error::Disconnected,
-        libssh_rs_sys::SSH_FX_CONNECTION_LOST => Error::Disconnected,
+        libssh_rs_sys::SSH_FX_NO_CONNECTION => Error::Disconnected { device: None, command: None },
+        libssh_rs_sys::SSH_FX_CONNECTION_LOST => Error::Disconnected { device: None, command: None },
         libssh_rs_sys::SSH_FX_NO_SUCH_PATH => Error::io(ErrorKind::NotFound),
         libssh_rs_sys::SSH_FX_FILE_ALREADY_EXISTS => Error::io(ErrorKind::AlreadyExists),
         libssh_rs_sys::SSH_FX_WRITE_PROTECT => Error::IO {
@@ -232,3 +453,84 @@ where
 {
     return serializer.serialize_str(&format!("{v:?}"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_retryable_transport_level_errors() {
+        assert!(Error::Disconnected { device: None, command: None }.is_retryable());
+        assert!(Error::Timeout.is_retryable());
+        assert!(Error::PoolFull.is_retryable());
+        for kind in [
+            ErrorKind::ConnectionRefused,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+            ErrorKind::NotConnected,
+            ErrorKind::TimedOut,
+            ErrorKind::Interrupted,
+            ErrorKind::WouldBlock,
+        ] {
+            let err = Error::IO { code: kind, message: String::new(), unhandled: false };
+            assert!(err.is_retryable(), "{kind:?} should be retryable");
+        }
+    }
+
+    #[test]
+    fn is_retryable_io_errors_outside_allowlist_are_not_retryable() {
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::PermissionDenied,
+            ErrorKind::AlreadyExists,
+            ErrorKind::InvalidInput,
+            ErrorKind::Other,
+        ] {
+            let err = Error::IO { code: kind, message: String::new(), unhandled: false };
+            assert!(!err.is_retryable(), "{kind:?} should not be retryable");
+        }
+    }
+
+    /// Every other variant represents something the device actively rejected or a programmer/
+    /// config error, neither of which a second attempt fixes — asserted variant by variant, so
+    /// each current variant's retryability is pinned down here rather than left to the
+    /// `_ => false` catch-all. A variant added later is not covered until it is listed here.
+    #[test]
+    fn is_retryable_device_rejected_and_terminal_errors_are_not_retryable() {
+        assert!(!Error::Authorization { message: String::new() }.is_retryable());
+        assert!(!Error::AuthFailed { methods_tried: vec![] }.is_retryable());
+        assert!(!Error::AuthMethodsExhausted.is_retryable());
+        assert!(!Error::BadPassphrase.is_retryable());
+        assert!(!Error::Cancelled.is_retryable());
+        assert!(!Error::CommandBlocked { command: String::new() }.is_retryable());
+        assert!(!Error::BadPrivateKey { message: String::new() }.is_retryable());
+        assert!(!Error::DevModeLikelyOff.is_retryable());
+        assert!(!Error::ElevationUnavailable.is_retryable());
+        assert!(!Error::HostKeyChanged { fingerprint: String::new() }.is_retryable());
+        assert!(!Error::InvalidHost { host: String::new() }.is_retryable());
+        assert!(!Error::UnknownHostKey { fingerprint: String::new() }.is_retryable());
+        assert!(!Error::ExitSignal { signal: String::new(), core_dumped: false }.is_retryable());
+        assert!(!Error::ExitStatus {
+            message: String::new(),
+            command: String::new(),
+            exit_code: 1,
+            stderr: vec![],
+            unhandled: false,
+            connection_id: None,
+            index: None,
+        }
+        .is_retryable());
+        assert!(!Error::Message { message: String::new(), unhandled: false }.is_retryable());
+        assert!(!Error::PassphraseRequired.is_retryable());
+        assert!(!Error::JsonParse { raw: String::new() }.is_retryable());
+        assert!(!Error::NotFound.is_retryable());
+        assert!(!Error::PartialFailure { paths: vec![] }.is_retryable());
+        assert!(!Error::OutputTooLarge { limit: 0 }.is_retryable());
+        assert!(!Error::Unsupported.is_retryable());
+        assert!(!Error::ChecksumMismatch { expected: String::new(), actual: String::new() }.is_retryable());
+        assert!(!Error::WebosTool { code: String::new(), detail: String::new() }.is_retryable());
+        assert!(!Error::JumpHostFailed { hop: String::new(), message: String::new() }.is_retryable());
+        assert!(!Error::DeadlineExceeded.is_retryable());
+        assert!(!Error::LunaCallFailed { uri: String::new(), error_text: String::new() }.is_retryable());
+    }
+}
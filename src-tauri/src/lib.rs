@@ -8,7 +8,7 @@ use std::path::PathBuf;
 use native_dialog::{MessageDialog, MessageType};
 use ssh_key::PrivateKey;
 use tauri::webview::PageLoadEvent;
-use tauri::{AppHandle, Manager, RunEvent, Runtime};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, Runtime};
 
 #[cfg(target_os = "android")]
 use android_logger::Config;
@@ -81,6 +81,15 @@ pub fn run() {
                         app.state::<DeviceManager>().set_conf_dir(conf_dir.clone());
                     }
                 }
+                RunEvent::ExitRequested { .. } => {
+                    let sessions = app.state::<SessionManager>();
+                    for entry in sessions.pool_status() {
+                        app.emit("device-disconnected", &entry.device_name)
+                            .unwrap_or(());
+                    }
+                    sessions.shutdown();
+                    app.state::<ShellManager>().close_all();
+                }
                 _ => {}
             });
             return Ok(());
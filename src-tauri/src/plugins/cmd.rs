@@ -1,16 +1,18 @@
-use std::io::{Read, Write};
 use std::sync::Arc;
 
 use serde::{Deserialize};
 use tauri::{
     plugin::{Builder, TauriPlugin},
-    AppHandle, Manager, Runtime, State,
+    AppHandle, Emitter, Manager, Runtime, State,
 };
 
+use crate::conn_pool::{ConnectionMetrics, DeviceInfo, DiskUsage, ProcessInfo, Sig};
 use crate::device_manager::Device;
 use crate::error::Error;
 use crate::event_channel::{EventChannel, EventHandler};
-use crate::session_manager::{Proc, ProcCallback, ProcData, SessionManager};
+use crate::session_manager::{
+    CommandPolicy, DeviceExecResult, PoolEntry, Proc, ProcCallback, ProcData, SessionManager,
+};
 use crate::spawn_manager::SpawnManager;
 
 #[tauri::command]
@@ -22,37 +24,325 @@ async fn exec<R: Runtime>(
 ) -> Result<Vec<u8>, Error> {
     return tokio::task::spawn_blocking(move || {
         let sessions = app.state::<SessionManager>();
-        return sessions.with_session(device, |session| {
-            let ch = session.new_channel()?;
-            ch.open_session()?;
-            ch.request_exec(&command)?;
-            if let Some(stdin) = stdin.clone() {
-                ch.stdin().write_all(&stdin)?;
-                ch.send_eof()?;
+        sessions.check_command(&command)?;
+        return sessions.with_session_audited(device, &command, |session| {
+            let output = session.exec(&command, stdin.as_deref())?;
+            session.mark_last_ok();
+            if output.status != 0 {
+                return Err(Error::exit_status(command.clone(), output.status, output.stderr.clone(), Some(session.id().to_string())));
             }
-            let mut buf = Vec::<u8>::new();
-            ch.stdout().read_to_end(&mut buf)?;
-            let mut stderr = Vec::<u8>::new();
-            ch.stderr().read_to_end(&mut stderr)?;
-            let exit_code = ch.get_exit_status().unwrap_or(0);
-            ch.close()?;
+            return Ok(output.stdout.clone());
+        });
+    })
+    .await
+    .unwrap();
+}
+
+/// Like `exec`, but runs `command` as root (already-root, or passwordless `sudo` if
+/// available), for maintenance tasks a dev-mode account can't run directly.
+#[tauri::command]
+async fn exec_elevated<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    command: String,
+    stdin: Option<Vec<u8>>,
+) -> Result<Vec<u8>, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        sessions.check_command(&command)?;
+        return sessions.with_session_audited(device, &command, |session| {
+            let output = session.exec_elevated(&command, stdin.as_deref())?;
             session.mark_last_ok();
-            if exit_code != 0 {
-                return Err(Error::ExitStatus {
-                    message: format!(""),
-                    command: command.clone(),
-                    exit_code,
-                    stderr,
-                    unhandled: true,
-                });
+            if output.status != 0 {
+                return Err(Error::exit_status(command.clone(), output.status, output.stderr.clone(), Some(session.id().to_string())));
             }
-            return Ok(buf);
+            return Ok(output.stdout.clone());
+        });
+    })
+    .await
+    .unwrap();
+}
+
+/// Like `exec`, but gives up as soon as [`cancel_exec`] is called with the same `id`, for a UI
+/// that needs to abandon a command mid-flight (e.g. the user closed the dialog that started it)
+/// instead of waiting for it to finish on its own.
+#[tauri::command]
+async fn exec_cancellable<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    command: String,
+    id: String,
+    stdin: Option<Vec<u8>>,
+) -> Result<Vec<u8>, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        sessions.check_command(&command)?;
+        let token = sessions.begin_cancellable(id.clone());
+        let result = sessions.with_session_audited(device, &command, |session| {
+            let output = session.exec_cancellable(&command, stdin.as_deref(), &token)?;
+            session.mark_last_ok();
+            if output.status != 0 {
+                return Err(Error::exit_status(command.clone(), output.status, output.stderr.clone(), Some(session.id().to_string())));
+            }
+            return Ok(output.stdout.clone());
+        });
+        sessions.end_cancellable(&id);
+        return result;
+    })
+    .await
+    .unwrap();
+}
+
+/// Trips `id`'s [`exec_cancellable`] call, if it's still running. A no-op if it already finished.
+#[tauri::command]
+async fn cancel_exec(sessions: State<'_, SessionManager>, id: String) -> Result<(), Error> {
+    sessions.cancel(&id);
+    return Ok(());
+}
+
+/// Like `exec`, but decodes stdout with `encoding` (a WHATWG charset label, e.g. `"gbk"`)
+/// instead of returning raw bytes, for devices/locales whose tools don't emit UTF-8.
+#[tauri::command]
+async fn exec_text<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    command: String,
+    encoding: Option<String>,
+) -> Result<String, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        sessions.check_command(&command)?;
+        return sessions.with_session_audited(device, &command, |session| {
+            let text = session.exec_text(&command, encoding.as_deref())?;
+            session.mark_last_ok();
+            return Ok(text);
         });
     })
     .await
     .unwrap();
 }
 
+/// Runs `command` on each of `devices` concurrently, capped at `max_concurrency` in flight at
+/// once, returning one result per device — a failure (or a blocked command, per the command
+/// policy) on one device doesn't stop or affect any other.
+#[tauri::command]
+async fn exec_on<R: Runtime>(
+    app: AppHandle<R>,
+    devices: Vec<Device>,
+    command: String,
+    max_concurrency: Option<usize>,
+) -> Result<Vec<DeviceExecResult>, Error> {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrency.unwrap_or(4).max(1)));
+    let mut handles = Vec::with_capacity(devices.len());
+    for device in devices {
+        let app = app.clone();
+        let command = command.clone();
+        let semaphore = semaphore.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.unwrap();
+            let device_name = device.name.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let sessions = app.state::<SessionManager>();
+                sessions.check_command(&command)?;
+                return sessions.with_session_audited(device, &command, |session| {
+                    let output = session.exec(&command, None)?;
+                    session.mark_last_ok();
+                    if output.status != 0 {
+                        return Err(Error::exit_status(command.clone(), output.status, output.stderr.clone(), Some(session.id().to_string())));
+                    }
+                    return Ok(output.stdout.clone());
+                });
+            })
+            .await
+            .unwrap();
+            return match result {
+                Ok(stdout) => DeviceExecResult {
+                    device_name,
+                    stdout: Some(stdout),
+                    error: None,
+                },
+                Err(e) => DeviceExecResult {
+                    device_name,
+                    stdout: None,
+                    error: Some(e),
+                },
+            };
+        }));
+    }
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.unwrap());
+    }
+    return Ok(results);
+}
+
+/// Like `exec`, but for workflows that legitimately expect a non-zero exit (e.g. checking
+/// whether a process exists via `pgrep`): returns stdout and the raw exit code without ever
+/// erroring on exit status, reserving `Err` for transport/channel failures.
+#[tauri::command]
+async fn exec_checked<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    command: String,
+    stdin: Option<Vec<u8>>,
+) -> Result<(Vec<u8>, i32), Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        sessions.check_command(&command)?;
+        return sessions.with_session_audited(device, &command, |session| {
+            let output = session.exec(&command, stdin.as_deref())?;
+            session.mark_last_ok();
+            return Ok((output.stdout.clone(), output.status));
+        });
+    })
+    .await
+    .unwrap();
+}
+
+/// Checks free space on `path`'s mount(s) before a large transfer (e.g. an IPK install), so
+/// the UI can warn up front instead of the install failing cryptically partway through.
+#[tauri::command]
+async fn disk_usage<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    path: String,
+) -> Result<Vec<DiskUsage>, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| session.disk_usage(&path));
+    })
+    .await
+    .unwrap();
+}
+
+/// Typed `/var/run/nyx/device_info.json` fields for `device`, for UI surfaces that want the
+/// model/firmware without re-parsing the raw JSON themselves.
+#[tauri::command]
+async fn device_info<R: Runtime>(app: AppHandle<R>, device: Device) -> Result<DeviceInfo, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| session.device_info());
+    })
+    .await
+    .unwrap();
+}
+
+#[tauri::command]
+async fn pool_status(sessions: State<'_, SessionManager>) -> Result<Vec<PoolEntry>, Error> {
+    return Ok(sessions.pool_status());
+}
+
+/// Lists `device`'s running processes, for a process-manager view letting Homebrew developers
+/// find and kill a stuck app.
+#[tauri::command]
+async fn list_processes<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+) -> Result<Vec<ProcessInfo>, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| session.list_processes());
+    })
+    .await
+    .unwrap();
+}
+
+/// Sends POSIX signal `sig` (e.g. `"TERM"`, `"KILL"`) to `pid` on `device`, for the process
+/// manager's kill action from [`list_processes`].
+#[tauri::command]
+async fn kill_process<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    pid: u32,
+    sig: Sig,
+) -> Result<(), Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        let command = format!("kill -{sig:?} {pid}");
+        return sessions.with_session_audited(device, &command, |session| session.kill(pid, sig));
+    })
+    .await
+    .unwrap();
+}
+
+/// Byte/command counters for `device`'s currently pooled connection, for diagnosing whether a
+/// slow install/transfer is actually progressing. Resets whenever the pool reconnects — r2d2
+/// doesn't expose enumerating or aggregating idle connections, so this reads whichever
+/// connection gets checked out rather than a running total across reconnects.
+#[tauri::command]
+async fn connection_metrics<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+) -> Result<ConnectionMetrics, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| Ok(session.metrics()));
+    })
+    .await
+    .unwrap();
+}
+
+/// Sets (or, with `None`, clears) the "safe mode" command allow-list: a list of regex patterns
+/// a command must match at least one of before `exec`/`exec_checked`/`spawn` will run it.
+#[tauri::command]
+async fn set_command_policy(
+    sessions: State<'_, SessionManager>,
+    patterns: Option<Vec<String>>,
+) -> Result<(), Error> {
+    let policy = match patterns {
+        Some(patterns) => Some(CommandPolicy::new(&patterns)?),
+        None => None,
+    };
+    sessions.set_command_policy(policy);
+    return Ok(());
+}
+
+/// Sets (or, with `None`, clears) a cap on the total pooled connections across every device;
+/// once set, the least-recently-used device's idle connections are evicted to make room for a
+/// new one rather than letting pooled connections grow unbounded.
+#[tauri::command]
+async fn set_max_connections(
+    sessions: State<'_, SessionManager>,
+    max: Option<u32>,
+) -> Result<(), Error> {
+    sessions.set_max_connections(max);
+    return Ok(());
+}
+
+/// Sets (or, with `None`, clears) the file every audited `exec`/spawned process is appended to
+/// as a JSON line, for compliance in a managed-lab deployment. Off by default.
+#[tauri::command]
+async fn set_audit_log(
+    sessions: State<'_, SessionManager>,
+    path: Option<String>,
+) -> Result<(), Error> {
+    return sessions.set_audit_log(path.map(std::path::PathBuf::from));
+}
+
+#[tauri::command]
+async fn ping<R: Runtime>(app: AppHandle<R>, device: Device) -> Result<u64, Error> {
+    let name = device.name.clone();
+    return tokio::task::spawn_blocking(move || {
+        let result = app.state::<SessionManager>().ping(device);
+        // A successful ping on a device that wasn't pooled before this call means we just
+        // (re)established the connection; either way it's live now, which is what the UI's
+        // status dot cares about.
+        if result.is_ok() {
+            app.emit("device-connected", &name).unwrap_or(());
+        }
+        return Ok(result?.as_millis() as u64);
+    })
+    .await
+    .unwrap();
+}
+
+#[tauri::command]
+async fn disconnect<R: Runtime>(app: AppHandle<R>, name: String) -> Result<(), Error> {
+    app.state::<SessionManager>().disconnect(&name);
+    app.emit("device-disconnected", &name).unwrap_or(());
+    return Ok(());
+}
+
 #[tauri::command]
 async fn spawn<R: Runtime>(
     app: AppHandle<R>,
@@ -60,10 +350,13 @@ async fn spawn<R: Runtime>(
     device: Device,
     command: String,
     managed: Option<bool>,
+    abort_on_drop: Option<bool>,
 ) -> Result<String, Error> {
+    sessions.check_command(&command)?;
     let channel = EventChannel::<R, ProcEventHandler>::new(app.clone(), "shell-proc");
     let token = channel.token();
     let proc = Arc::new(sessions.spawn(device, &command));
+    proc.set_abort_on_drop(abort_on_drop.unwrap_or(false));
     channel.listen(ProcEventHandler { proc: proc.clone() });
     tokio::task::spawn_blocking(move || proc_worker(app, proc, channel, managed.unwrap_or(true)));
     return Ok(token);
@@ -112,9 +405,10 @@ struct TxPayload {
 }
 
 impl<R: Runtime> ProcCallback for ProcCallbackImpl<R> {
-    fn rx(&self, fd: u32, data: &[u8]) {
+    fn rx(&self, fd: u32, seq: u64, data: &[u8]) {
         self.channel.rx(ProcData {
             fd,
+            seq,
             data: Vec::<u8>::from(data),
         });
     }
@@ -143,6 +437,26 @@ impl EventHandler for ProcEventHandler {
 /// Initializes the plugin.
 pub fn plugin<R: Runtime>(name: &'static str) -> TauriPlugin<R> {
     Builder::new(name)
-        .invoke_handler(tauri::generate_handler![exec, spawn])
+        .invoke_handler(tauri::generate_handler![
+            exec,
+            exec_elevated,
+            exec_cancellable,
+            cancel_exec,
+            exec_text,
+            exec_checked,
+            exec_on,
+            spawn,
+            disk_usage,
+            connection_metrics,
+            device_info,
+            pool_status,
+            list_processes,
+            kill_process,
+            ping,
+            disconnect,
+            set_command_policy,
+            set_max_connections,
+            set_audit_log
+        ])
         .build()
 }
@@ -1,24 +1,77 @@
+use std::time::Duration;
+
 use tauri::{
     plugin::{Builder, TauriPlugin},
-    Runtime,
+    Emitter, Manager, Runtime,
 };
 use tauri::{AppHandle, State};
 
 use crate::app_dirs::{GetAppSshKeyDir, GetSshDir};
-use crate::device_manager::{Device, DeviceCheckConnection, DeviceManager};
+use crate::device_manager::{
+    Device, DeviceCheckConnection, DeviceManager, DevicePatch, DeviceProbe, DiscoveredDevice,
+    ImportPreview,
+};
 use crate::error::Error;
+use crate::session_manager::SessionManager;
 
 #[tauri::command]
 async fn list(manager: State<'_, DeviceManager>) -> Result<Vec<Device>, Error> {
     return manager.list().await;
 }
 
+#[tauri::command]
+async fn get(manager: State<'_, DeviceManager>, name: String) -> Result<Option<Device>, Error> {
+    return manager.get(&name).await;
+}
+
 #[tauri::command]
 async fn set_default(
     manager: State<'_, DeviceManager>,
-    name: String,
+    name: Option<String>,
 ) -> Result<Option<Device>, Error> {
-    return manager.set_default(&name).await;
+    return manager.set_default(name.as_deref()).await;
+}
+
+#[tauri::command]
+async fn clear_default(manager: State<'_, DeviceManager>) -> Result<Option<Device>, Error> {
+    return manager.clear_default().await;
+}
+
+#[tauri::command]
+async fn trust_host_key(
+    manager: State<'_, DeviceManager>,
+    name: String,
+    fingerprint: String,
+) -> Result<(), Error> {
+    return manager.trust_host_key(&name, &fingerprint).await;
+}
+
+#[tauri::command]
+async fn rename<R: Runtime>(
+    app: AppHandle<R>,
+    manager: State<'_, DeviceManager>,
+    sessions: State<'_, SessionManager>,
+    old: String,
+    new: String,
+) -> Result<(), Error> {
+    manager.rename(&old, &new).await?;
+    sessions.disconnect(&old);
+    app.emit("device-disconnected", &old).unwrap_or(());
+    return Ok(());
+}
+
+#[tauri::command]
+async fn update<R: Runtime>(
+    app: AppHandle<R>,
+    manager: State<'_, DeviceManager>,
+    sessions: State<'_, SessionManager>,
+    name: String,
+    patch: DevicePatch,
+) -> Result<Device, Error> {
+    let device = manager.update(&name, patch).await?;
+    sessions.disconnect(&name);
+    app.emit("device-disconnected", &name).unwrap_or(());
+    return Ok(device);
 }
 
 #[tauri::command]
@@ -26,13 +79,30 @@ async fn add(manager: State<'_, DeviceManager>, device: Device) -> Result<Device
     return manager.add(&device).await;
 }
 
+/// Removing an already-removed device is a no-op, not an error — `DeviceManager::remove`
+/// itself is idempotent since it merely filters the device out of the list it's about to
+/// rewrite. What's not idempotent without help is the live session: evict it and notify the
+/// frontend the same way `rename`/`update` do, in case this is the second of a double-click.
 #[tauri::command]
-async fn remove(
+async fn remove<R: Runtime>(
+    app: AppHandle<R>,
     manager: State<'_, DeviceManager>,
+    sessions: State<'_, SessionManager>,
     name: String,
     remove_key: bool,
 ) -> Result<(), Error> {
-    return manager.remove(&name, remove_key).await;
+    manager.remove(&name, remove_key).await?;
+    sessions.disconnect(&name);
+    app.emit("device-disconnected", &name).unwrap_or(());
+    return Ok(());
+}
+
+#[tauri::command]
+async fn remove_preview(
+    manager: State<'_, DeviceManager>,
+    name: String,
+) -> Result<Vec<Device>, Error> {
+    return manager.remove_preview(&name).await;
 }
 
 #[tauri::command]
@@ -65,6 +135,14 @@ async fn privkey_read<R: Runtime>(app: AppHandle<R>, device: Device) -> Result<S
         .content(app.get_ssh_dir().as_deref())?);
 }
 
+#[tauri::command]
+async fn test_connection(
+    manager: State<'_, DeviceManager>,
+    device: Device,
+) -> Result<DeviceProbe, Error> {
+    return manager.test_connection(&device).await;
+}
+
 #[tauri::command]
 async fn check_connection(
     manager: State<'_, DeviceManager>,
@@ -73,6 +151,84 @@ async fn check_connection(
     return manager.check_connection(&host).await;
 }
 
+#[tauri::command]
+async fn discover(
+    manager: State<'_, DeviceManager>,
+    timeout_ms: u64,
+) -> Result<Vec<DiscoveredDevice>, Error> {
+    return manager.discover(Duration::from_millis(timeout_ms)).await;
+}
+
+/// Establishes connections to `names` (or, if empty, whichever device is marked default)
+/// concurrently in the background, so the first real command a user runs doesn't pay for the
+/// initial handshake. Failures (a TV that's powered off, say) are logged and otherwise ignored
+/// rather than surfaced — this is a best-effort warm-up, not something the caller should have
+/// to handle errors for. `SessionManager`'s own pool/idle-eviction rules apply to the resulting
+/// connections exactly as they would to any other, so one that's never used still ages out.
+#[tauri::command]
+async fn prewarm<R: Runtime>(app: AppHandle<R>, names: Vec<String>) -> Result<(), Error> {
+    let devices = app.state::<DeviceManager>().list().await?;
+    let targets: Vec<Device> = if names.is_empty() {
+        devices
+            .into_iter()
+            .filter(|d| d.default.unwrap_or(false))
+            .collect()
+    } else {
+        devices.into_iter().filter(|d| names.contains(&d.name)).collect()
+    };
+    let mut handles = Vec::with_capacity(targets.len());
+    for device in targets {
+        let app = app.clone();
+        handles.push(tokio::task::spawn_blocking(move || {
+            let name = device.name.clone();
+            if let Err(e) = app.state::<SessionManager>().ping(device) {
+                log::warn!("Failed to prewarm connection to {name:?}: {e:?}");
+            }
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap_or(());
+    }
+    return Ok(());
+}
+
+/// Builds a not-yet-saved `Device` from the matching `Host` block in `~/.ssh/config`, for
+/// prefilling the add-device form from a user's existing SSH tooling rather than making them
+/// retype a host/port/identity file they've already configured.
+#[tauri::command]
+async fn from_ssh_config(
+    manager: State<'_, DeviceManager>,
+    alias: String,
+) -> Result<Device, Error> {
+    return manager.from_ssh_config(&alias);
+}
+
+#[tauri::command]
+async fn export(
+    manager: State<'_, DeviceManager>,
+    include_secrets: bool,
+) -> Result<String, Error> {
+    return manager.export(include_secrets).await;
+}
+
+#[tauri::command]
+async fn import(
+    manager: State<'_, DeviceManager>,
+    json: String,
+    merge: bool,
+) -> Result<Vec<Device>, Error> {
+    return manager.import(&json, merge).await;
+}
+
+#[tauri::command]
+async fn import_preview(
+    manager: State<'_, DeviceManager>,
+    json: String,
+    merge: bool,
+) -> Result<ImportPreview, Error> {
+    return manager.import_preview(&json, merge).await;
+}
+
 #[tauri::command]
 async fn app_ssh_key_path<R: Runtime>(app: AppHandle<R>) -> Result<String, Error> {
     return Ok(app.ensure_app_ssh_key_path()?.to_string_lossy().to_string());
@@ -88,13 +244,26 @@ pub fn plugin<R: Runtime>(name: &'static str) -> TauriPlugin<R> {
     Builder::new(name)
         .invoke_handler(tauri::generate_handler![
             list,
+            get,
             set_default,
+            clear_default,
             add,
+            update,
+            rename,
+            trust_host_key,
             remove,
+            remove_preview,
             novacom_getkey,
             localkey_verify,
             privkey_read,
+            test_connection,
             check_connection,
+            discover,
+            prewarm,
+            from_ssh_config,
+            export,
+            import,
+            import_preview,
             app_ssh_key_path,
             app_ssh_pubkey,
         ])
@@ -14,7 +14,7 @@ use uuid::Uuid;
 use crate::device_manager::Device;
 use crate::error::Error;
 use crate::remote_files::serve;
-use crate::remote_files::{FileItem, PermInfo};
+use crate::remote_files::FileItem;
 use crate::session_manager::SessionManager;
 
 #[derive(Clone, Serialize)]
@@ -35,16 +35,7 @@ async fn ls<R: Runtime>(
     log::info!("ls {}", path);
     return tokio::task::spawn_blocking(move || {
         let sessions = app.state::<SessionManager>();
-        return sessions.with_session(device, |session| {
-            let sftp = session.sftp()?;
-            let entries = sftp.read_dir(&path)?;
-            let user = session.user.as_ref();
-            return Ok(entries
-                .iter()
-                .filter(|entry| entry.name() != Some(".") && entry.name() != Some(".."))
-                .map(|entry| FileItem::new(entry, None, user.map(|u| PermInfo::from(entry, &u))))
-                .collect());
-        });
+        return sessions.with_session(device, |session| session.list_dir(&path));
     })
     .await
     .expect("critical failure in file::ls task");
@@ -101,6 +92,84 @@ async fn write<R: Runtime>(
     .expect("critical failure in file::write task");
 }
 
+/// Reads the whole contents of `path` into memory in one round trip, refusing anything past
+/// `max_len` instead of buffering an arbitrarily large file — for small reads (config files,
+/// JSON) where a full [`get`]-to-disk download would be overkill.
+#[tauri::command]
+async fn read_file<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    path: String,
+    max_len: usize,
+) -> Result<Vec<u8>, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| session.read_file(&path, max_len));
+    })
+    .await
+    .expect("critical failure in file::read_file task");
+}
+
+/// Writes `content` to `path` in one round trip, atomically (write to a temp name, then
+/// rename into place) — the write counterpart to [`read_file`], for saving a small edited
+/// config blob without a local temp file.
+#[tauri::command]
+async fn write_file<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    path: String,
+    content: Vec<u8>,
+    mode: u32,
+    create_parents: bool,
+) -> Result<(), Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| {
+            session.write_file(&path, &content, mode, create_parents)
+        });
+    })
+    .await
+    .expect("critical failure in file::write_file task");
+}
+
+/// Moves/renames `from` to `to`, falling back to copy+delete across filesystems. Returns
+/// whether `to` already existed and was overwritten.
+#[tauri::command]
+async fn rename<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    from: String,
+    to: String,
+) -> Result<bool, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        return sessions.with_session(device, |session| session.rename(&from, &to));
+    })
+    .await
+    .expect("critical failure in file::rename task");
+}
+
+/// Like [`put`], but verifies the transfer by comparing a local sha256 against one computed on
+/// the device afterward, for callers (e.g. pushing an IPK) who want certainty it arrived intact.
+#[tauri::command]
+async fn put_verified<R: Runtime>(
+    app: AppHandle<R>,
+    device: Device,
+    path: String,
+    source: String,
+    rate_limit: Option<u64>,
+) -> Result<u64, Error> {
+    return tokio::task::spawn_blocking(move || {
+        let sessions = app.state::<SessionManager>();
+        let source = Path::new(&source);
+        return sessions.with_session(device, |session| {
+            session.upload_verified(source, &path, rate_limit)
+        });
+    })
+    .await
+    .expect("critical failure in file::put_verified task");
+}
+
 #[tauri::command]
 async fn get<R: Runtime>(
     app: AppHandle<R>,
@@ -236,7 +305,7 @@ async fn serve<R: Runtime>(
 pub fn plugin<R: Runtime>(name: &'static str) -> TauriPlugin<R> {
     Builder::new(name)
         .invoke_handler(tauri::generate_handler![
-            ls, read, write, get, put, get_temp, serve
+            ls, read, write, read_file, write_file, rename, get, put, put_verified, get_temp, serve
         ])
         .build()
 }
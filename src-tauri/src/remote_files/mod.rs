@@ -28,3 +28,15 @@ pub struct PermInfo {
     write: bool,
     execute: bool,
 }
+
+/// Raw `stat`/`lstat` result for a remote path. webOS's uid/gid mapping is minimal, so these
+/// are passed through as the numeric ids the device reports rather than resolved names.
+#[derive(Serialize, Clone, Debug)]
+pub struct FileStat {
+    size: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    mtime: f64,
+    is_symlink: bool,
+}
@@ -62,7 +62,7 @@ fn serve_worker<R: Runtime>(
                 break;
             }
             Err(e) => match Error::from(e) {
-                Error::Disconnected => continue,
+                Error::Disconnected { .. } => continue,
                 e => return Err(e),
             },
         }
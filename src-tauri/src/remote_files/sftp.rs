@@ -3,7 +3,7 @@ use std::time::UNIX_EPOCH;
 use crate::conn_pool::DeviceConnectionUserInfo;
 use libssh_rs::{FileType, Metadata};
 
-use crate::remote_files::{FileItem, LinkInfo, PermInfo};
+use crate::remote_files::{FileItem, FileStat, LinkInfo, PermInfo};
 
 impl From<&Metadata> for FileItem {
     fn from(stat: &Metadata) -> Self {
@@ -35,6 +35,24 @@ impl FileItem {
     }
 }
 
+impl FileStat {
+    pub(crate) fn new(stat: &Metadata, is_symlink: bool) -> Self {
+        return FileStat {
+            size: stat.len().unwrap_or(0),
+            mode: stat.permissions().unwrap_or(0),
+            uid: stat.uid().unwrap_or(0),
+            gid: stat.gid().unwrap_or(0),
+            mtime: stat
+                .modified()
+                .unwrap_or(UNIX_EPOCH)
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64(),
+            is_symlink,
+        };
+    }
+}
+
 impl PermInfo {
     pub fn from(stat: &Metadata, user: &DeviceConnectionUserInfo) -> Self {
         let perms = stat.permissions().unwrap_or(0);
@@ -0,0 +1,90 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::session_manager::SessionManager;
+
+/// One entry in the audit log, appended by [`SessionManager::record_audit`] — one row per
+/// `exec`/[`crate::session_manager::Proc`] run, for compliance in a managed-lab deployment
+/// where every command run against a device needs to be traceable after the fact. A `Proc`
+/// run through `Proc::wait_close_deadline` is not recorded.
+#[derive(Serialize)]
+pub(crate) struct AuditRecord {
+    timestamp_ms: u128,
+    device: String,
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_status: Option<i32>,
+    duration_ms: u128,
+}
+
+impl SessionManager {
+    /// Sets (or, with `None`, clears) the file every audited command is appended to as a JSON
+    /// line. Opens `path` for appending and hands writes off to a dedicated background thread
+    /// over a channel, so a slow disk never adds latency to the command path itself; replacing
+    /// or clearing the sink drops the old channel's sender, which lets its thread exit once it
+    /// finishes draining whatever was already queued.
+    pub fn set_audit_log(&self, path: Option<PathBuf>) -> Result<(), Error> {
+        let sender = match path {
+            Some(path) => Some(Self::spawn_audit_writer(path)?),
+            None => None,
+        };
+        *self
+            .audit
+            .lock()
+            .expect("Failed to lock SessionManager::audit") = sender;
+        return Ok(());
+    }
+
+    fn spawn_audit_writer(path: PathBuf) -> Result<Sender<AuditRecord>, Error> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (sender, receiver) = channel::<AuditRecord>();
+        std::thread::spawn(move || {
+            while let Ok(record) = receiver.recv() {
+                let Ok(mut line) = serde_json::to_string(&record) else {
+                    continue;
+                };
+                line.push('\n');
+                if let Err(e) = file.write_all(line.as_bytes()) {
+                    log::warn!("Failed to write audit record: {e:?}");
+                }
+            }
+        });
+        return Ok(sender);
+    }
+
+    /// Appends one audit record if a sink is configured (see
+    /// [`SessionManager::set_audit_log`]); a no-op otherwise, so call sites don't need to check
+    /// whether auditing is even enabled. `command` is redacted the same way `exec`'s own debug
+    /// log is, so a command embedding a password never ends up on disk either way.
+    pub(crate) fn record_audit(
+        &self,
+        device: &str,
+        command: &str,
+        exit_status: Option<i32>,
+        duration: Duration,
+    ) {
+        let audit = self
+            .audit
+            .lock()
+            .expect("Failed to lock SessionManager::audit");
+        if let Some(sender) = audit.as_ref() {
+            let record = AuditRecord {
+                timestamp_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis(),
+                device: device.to_string(),
+                command: crate::conn_pool::connection::redact_secrets(command),
+                exit_status,
+                duration_ms: duration.as_millis(),
+            };
+            sender.send(record).unwrap_or(());
+        }
+    }
+}
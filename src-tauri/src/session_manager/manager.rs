@@ -1,30 +1,72 @@
 use std::path::PathBuf;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+use crate::conn_pool::{CancelToken, DeviceConnectionPool, ManagedDeviceConnection};
 
-use crate::conn_pool::{DeviceConnectionPool, ManagedDeviceConnection};
 use crate::device_manager::Device;
 use crate::error::Error;
-use crate::session_manager::{Proc, SessionManager};
+use crate::session_manager::{CommandPolicy, PoolEntry, Proc, SessionManager};
 use crate::app_dirs::{GetSshDir, SetSshDir};
 
 impl SessionManager {
     pub fn session(&self, device: Device) -> Result<ManagedDeviceConnection, Error> {
+        self.ensure_capacity(&Self::pool_key(&device))?;
         return self.pool(device).get();
     }
 
+    /// Lists each known device's pool size/idle counts, for an "active sessions" debug panel.
+    pub fn pool_status(&self) -> Vec<PoolEntry> {
+        return self
+            .pools
+            .lock()
+            .expect("Failed to lock SessionManager::pools")
+            .iter()
+            .map(|(name, pool)| {
+                let state = pool.state();
+                PoolEntry {
+                    device_name: name.clone(),
+                    connections: state.connections,
+                    idle_connections: state.idle_connections,
+                }
+            })
+            .collect();
+    }
+
+    /// Retries we'll absorb silently before surfacing a connection-level failure to the
+    /// caller. TVs occasionally drop the SSH session mid-command; a couple of transparent
+    /// reconnect attempts let most of those self-heal instead of bubbling up as an error.
+    const RECONNECT_RETRIES: u32 = 2;
+
     pub fn with_session<T, F>(&self, device: Device, action: F) -> Result<T, Error>
     where
         F: Fn(&ManagedDeviceConnection) -> Result<T, Error>,
     {
+        let pool_key = Self::pool_key(&device);
         let pool = self.pool(device);
+        let mut attempt = 0;
         loop {
-            let session = pool.get()?;
+            // Re-checked on every attempt, not just the first, so a transient `PoolFull` (every
+            // pool briefly full right as one finishes evicting, or in use by another thread)
+            // gets the same retry/backoff treatment as a dropped session instead of failing the
+            // whole call on the first pass.
+            let session = match self.ensure_capacity(&pool_key).and_then(|_| pool.get()) {
+                Ok(session) => session,
+                Err(e) if e.is_retryable() && attempt < Self::RECONNECT_RETRIES => {
+                    attempt += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(200 * attempt as u64));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             return match action(&session) {
                 Ok(ret) => {
                     session.mark_last_ok();
                     Ok(ret)
                 }
-                Err(Error::Disconnected) => {
+                Err(e) if e.is_retryable() && attempt < Self::RECONNECT_RETRIES => {
+                    attempt += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(200 * attempt as u64));
                     continue;
                 }
                 Err(e) => Err(e),
@@ -32,30 +74,331 @@ impl SessionManager {
         }
     }
 
+    /// Like [`Self::with_session`], but also appends one [`Self::record_audit`] entry — success
+    /// or failure — and tags a bare [`Error::Disconnected`] with `device`/`command` via
+    /// [`Error::with_disconnect_context`]. This is the one place every exec-family plugin
+    /// command should go through, so the audit log's "every command run against a device"
+    /// guarantee doesn't depend on each command remembering to record itself.
+    pub fn with_session_audited<T, F>(
+        &self,
+        device: Device,
+        command: &str,
+        action: F,
+    ) -> Result<T, Error>
+    where
+        F: Fn(&ManagedDeviceConnection) -> Result<T, Error>,
+    {
+        let device_name = device.name.clone();
+        let started = Instant::now();
+        let result = self
+            .with_session(device, action)
+            .map_err(|e| e.with_disconnect_context(&device_name, command));
+        let exit_status = match &result {
+            Ok(_) => Some(0),
+            Err(Error::ExitStatus { exit_code, .. }) => Some(*exit_code),
+            Err(_) => None,
+        };
+        self.record_audit(&device_name, command, exit_status, started.elapsed());
+        return result;
+    }
+
+    /// Sets (or, with `None`, clears) a cap on the total pooled connections across every
+    /// device. Once in place, [`Self::ensure_capacity`] enforces it before every connection
+    /// checkout, evicting the least-recently-used pool's idle connections to make room.
+    pub fn set_max_connections(&self, max: Option<u32>) {
+        *self
+            .max_connections
+            .lock()
+            .expect("Failed to lock SessionManager::max_connections") = max;
+    }
+
+    /// Sum of `connections` (not just idle ones) across every device's pool, for comparing
+    /// against `max_connections` — except `pool_key`'s own idle connections, which checkout is
+    /// about to reuse rather than grow, so they shouldn't count against the cap it's about to
+    /// check out under. Without this exclusion, a single device whose pool has filled up to
+    /// exactly `max` idle connections (the common one-device case) would find no other pool to
+    /// evict and permanently report [`Error::PoolFull`] even though it has idle connections of
+    /// its own sitting right there to reuse.
+    fn total_connections(&self, pool_key: &str) -> u32 {
+        let pools = self
+            .pools
+            .lock()
+            .expect("Failed to lock SessionManager::pools");
+        let own_idle = pools
+            .get(pool_key)
+            .map(|p| p.state().idle_connections)
+            .unwrap_or(0);
+        let total: u32 = pools.values().map(|p| p.state().connections).sum();
+        return total.saturating_sub(own_idle);
+    }
+
+    /// Enforces `max_connections`, if set, before `pool_key` checks out a connection: while the
+    /// total is at or over the cap, evicts whichever *other* pool was least recently used and
+    /// is currently wholly idle (nothing checked out of it), the same way [`Self::disconnect`]
+    /// drops a pool — r2d2 doesn't expose evicting individual idle connections out of a live
+    /// pool, so a pool that's partway in use is left alone rather than risking a connection a
+    /// caller is actively holding. Once nothing idle is left to evict, every pooled connection
+    /// is genuinely in use, so this returns [`Error::PoolFull`] rather than blocking
+    /// indefinitely.
+    fn ensure_capacity(&self, pool_key: &str) -> Result<(), Error> {
+        let max = match *self
+            .max_connections
+            .lock()
+            .expect("Failed to lock SessionManager::max_connections")
+        {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        while self.total_connections(pool_key) >= max {
+            let last_used = self
+                .pool_last_used
+                .lock()
+                .expect("Failed to lock SessionManager::pool_last_used")
+                .clone();
+            let victim = self
+                .pools
+                .lock()
+                .expect("Failed to lock SessionManager::pools")
+                .iter()
+                .filter(|(key, pool)| {
+                    let state = pool.state();
+                    key.as_str() != pool_key
+                        && state.connections > 0
+                        && state.connections == state.idle_connections
+                })
+                .min_by_key(|(key, _)| last_used.get(*key).copied().unwrap_or_else(Instant::now))
+                .map(|(key, _)| key.to_string());
+            match victim {
+                Some(key) => {
+                    log::info!(
+                        "Evicting idle pool for {key:?} to stay under max_connections={max}"
+                    );
+                    self.pools
+                        .lock()
+                        .expect("Failed to lock SessionManager::pools")
+                        .remove(&key);
+                    self.pool_last_used
+                        .lock()
+                        .expect("Failed to lock SessionManager::pool_last_used")
+                        .remove(&key);
+                }
+                None => return Err(Error::PoolFull),
+            }
+        }
+        self.pool_last_used
+            .lock()
+            .expect("Failed to lock SessionManager::pool_last_used")
+            .insert(pool_key.to_string(), Instant::now());
+        return Ok(());
+    }
+
+    /// Registers a fresh [`CancelToken`] under `id` for an in-flight `exec_cancellable` call,
+    /// so a later [`Self::cancel`] with the same `id` can reach it from another command
+    /// invocation. Overwrites whatever was previously registered under `id`, if anything.
+    pub fn begin_cancellable(&self, id: String) -> CancelToken {
+        let token = CancelToken::new();
+        self.cancellable
+            .lock()
+            .expect("Failed to lock SessionManager::cancellable")
+            .insert(id, token.clone());
+        return token;
+    }
+
+    /// Trips the [`CancelToken`] registered under `id`, if one still is — a no-op if the call it
+    /// belonged to already finished and cleaned up via [`Self::end_cancellable`].
+    pub fn cancel(&self, id: &str) {
+        if let Some(token) = self
+            .cancellable
+            .lock()
+            .expect("Failed to lock SessionManager::cancellable")
+            .get(id)
+        {
+            token.cancel();
+        }
+    }
+
+    /// Unregisters `id`'s [`CancelToken`] once its `exec_cancellable` call has returned, so
+    /// [`Self::cancel`] can't be called against a call that no longer exists.
+    pub fn end_cancellable(&self, id: &str) {
+        self.cancellable
+            .lock()
+            .expect("Failed to lock SessionManager::cancellable")
+            .remove(id);
+    }
+
+    /// Sets (or, with `None`, clears) the allow-list every command must satisfy before this
+    /// manager will run it. Applies to every device, not just one.
+    pub fn set_command_policy(&self, policy: Option<CommandPolicy>) {
+        *self
+            .command_policy
+            .lock()
+            .expect("Failed to lock SessionManager::command_policy") = policy;
+    }
+
+    /// Checks `command` against the configured allow-list, if any. Call this before building
+    /// anything that would run `command`, so a blocked command never opens a channel.
+    pub fn check_command(&self, command: &str) -> Result<(), Error> {
+        let policy = self
+            .command_policy
+            .lock()
+            .expect("Failed to lock SessionManager::command_policy");
+        if let Some(policy) = policy.as_ref() {
+            if !policy.allow.iter().any(|re| re.is_match(command)) {
+                return Err(Error::CommandBlocked {
+                    command: command.to_string(),
+                });
+            }
+        }
+        return Ok(());
+    }
+
     pub fn spawn(&self, device: Device, command: &str) -> Proc {
+        return self.spawn_with_pty(device, command, false);
+    }
+
+    pub fn spawn_with_pty(&self, device: Device, command: &str, pty: bool) -> Proc {
         return Proc {
             device,
             command: String::from(command),
+            pty,
             callback: Mutex::default(),
             ready: Arc::new((Mutex::default(), Condvar::new())),
             sender: Mutex::default(),
             interrupted: Mutex::new(false),
+            seq: Mutex::new(0),
+            abort_on_drop: Mutex::new(false),
+        };
+    }
+
+    /// Follows `path` on the device, built on the same `Proc` machinery as [`Self::spawn`] —
+    /// install a `LineCallback` on the returned `Proc` to get per-line output, and interrupt
+    /// it with `Proc::interrupt` to stop following. When `follow` is true this uses `tail
+    /// -F`, which waits for (and re-opens) a file that doesn't exist yet rather than erroring,
+    /// so it's safe to call before the log's producing service has started.
+    pub fn tail(&self, device: Device, path: &str, follow: bool) -> Proc {
+        let quoted = format!("'{}'", path.replace('\'', "'\"'\"'"));
+        let command = if follow {
+            format!("tail -F -n 10 -- {quoted}")
+        } else {
+            format!("tail -n 10 -- {quoted}")
+        };
+        return self.spawn(device, &command);
+    }
+
+    /// Pings `device`'s pooled connection and returns the round-trip time. If the channel
+    /// can't even be opened, the pooled connection is evicted so the next call reconnects
+    /// instead of repeatedly hitting the same dead handle.
+    pub fn ping(&self, device: Device) -> Result<std::time::Duration, Error> {
+        let name = device.name.clone();
+        let pool = self.pool(device);
+        return match pool.get().and_then(|session| session.ping()) {
+            Ok(rtt) => Ok(rtt),
+            Err(e) => {
+                self.disconnect(&name);
+                Err(e)
+            }
+        };
+    }
+
+    /// Drops the pooled connection(s) for `name`, if any, so a stale session (e.g. after
+    /// credentials changed) is gone immediately instead of surviving until its next
+    /// liveness check fails.
+    pub fn disconnect(&self, name: &str) {
+        self.pools
+            .lock()
+            .expect("Failed to lock SessionManager::pools")
+            .remove(name);
+    }
+
+    /// Tears down every pooled connection, on app exit. Dropping each pool drops its
+    /// connections in turn, closing the underlying SSH session cleanly rather than just
+    /// abandoning the socket. Safe to call more than once — clearing an already-empty map
+    /// is a no-op.
+    pub fn shutdown(&self) {
+        self.pools
+            .lock()
+            .expect("Failed to lock SessionManager::pools")
+            .clear();
+    }
+
+    /// Retries establishing `device`'s pooled connection up to `attempts` times, waiting
+    /// `base * 2^(attempt - 1)` (`attempt` 0-based) plus up to 100ms of jitter before each retry
+    /// so a fleet of devices reconnecting at once (e.g. after the desktop wakes from sleep)
+    /// doesn't hammer the network in lockstep. Returns the last error if every attempt fails.
+    /// No other code in this crate calls it: [`Self::with_session`]'s retry loop sleeps its own
+    /// `200ms * attempt` between tries instead.
+    pub fn reconnect_with_backoff(
+        &self,
+        device: Device,
+        attempts: u32,
+        base: std::time::Duration,
+    ) -> Result<ManagedDeviceConnection, Error> {
+        return self.reconnect_with_backoff_notify(device, attempts, base, |_, _| {});
+    }
+
+    /// Like [`Self::reconnect_with_backoff`], but calls `on_attempt(attempt, attempts)` before
+    /// each try (`attempt` is 0-based), so a caller can surface "reconnecting (2/5)..." progress
+    /// to the UI instead of the retry loop running silently until it either succeeds or exhausts
+    /// its attempts.
+    pub fn reconnect_with_backoff_notify<F>(
+        &self,
+        device: Device,
+        attempts: u32,
+        base: std::time::Duration,
+        on_attempt: F,
+    ) -> Result<ManagedDeviceConnection, Error>
+    where
+        F: Fn(u32, u32),
+    {
+        let mut last_err = Error::Disconnected {
+            device: Some(device.name.clone()),
+            command: None,
         };
+        for attempt in 0..attempts {
+            on_attempt(attempt, attempts);
+            if attempt > 0 {
+                let backoff = base * 2u32.pow(attempt - 1);
+                let jitter = std::time::Duration::from_millis(rand::random::<u64>() % 100);
+                log::debug!(
+                    "reconnect_with_backoff: attempt {attempt} for {:?} in {:?}",
+                    device.name,
+                    backoff + jitter
+                );
+                std::thread::sleep(backoff + jitter);
+            }
+            match self.pool(device.clone()).get() {
+                Ok(conn) => return Ok(conn),
+                Err(e) => last_err = e,
+            }
+        }
+        return Err(last_err);
+    }
+
+    /// The key under which `device`'s pool lives in `self.pools`. Normally its name, so each
+    /// registered device gets its own pool even if two happen to share a host; with
+    /// `shared_connection` set, its `host:port:username` instead, so multiple device entries
+    /// for the same credentials on the same host reuse one pooled connection rather than each
+    /// opening their own.
+    fn pool_key(device: &Device) -> String {
+        if device.shared_connection.unwrap_or(false) {
+            return format!("{}:{}:{}", device.host, device.port, device.username);
+        }
+        return device.name.clone();
     }
 
     fn pool(&self, device: Device) -> DeviceConnectionPool {
         if device.new {
             return DeviceConnectionPool::new(device, self.get_ssh_dir());
         }
+        let key = Self::pool_key(&device);
         if let Some(p) = self
             .pools
             .lock()
             .expect("Failed to lock SessionManager::pools")
-            .get(&device.name)
+            .get(&key)
         {
             return p.clone();
         }
-        let key = device.name.clone();
         let pool = DeviceConnectionPool::new(device, self.get_ssh_dir());
         self.pools
             .lock()
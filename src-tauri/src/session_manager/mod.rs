@@ -2,12 +2,16 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
 
+use regex::Regex;
 use serde::Serialize;
 
-use crate::conn_pool::DeviceConnectionPool;
+use crate::conn_pool::{CancelToken, DeviceConnectionPool};
 use crate::device_manager::Device;
+use crate::error::Error;
 
+mod audit;
 mod manager;
 mod proc;
 
@@ -15,20 +19,96 @@ mod proc;
 pub struct SessionManager {
     ssh_dir: Mutex<Option<PathBuf>>,
     pools: Mutex<HashMap<String, DeviceConnectionPool>>,
+    command_policy: Mutex<Option<CommandPolicy>>,
+    /// When set, [`SessionManager::ensure_capacity`] caps the sum of every device's pooled
+    /// connections to this many, evicting the least-recently-used pool's idle connections to
+    /// make room rather than letting file descriptor/server-slot usage grow unbounded on a
+    /// machine managing many TVs.
+    max_connections: Mutex<Option<u32>>,
+    /// Last time each pool (keyed the same way as `pools`) was accessed, for
+    /// [`SessionManager::ensure_capacity`] to pick an eviction candidate.
+    pool_last_used: Mutex<HashMap<String, Instant>>,
+    /// Channel to the background audit-log writer thread, if [`SessionManager::set_audit_log`]
+    /// has been called with a path. `None` (the default) means auditing is off.
+    audit: Mutex<Option<Sender<audit::AuditRecord>>>,
+    /// Tokens for in-flight [`crate::plugins::cmd`] `exec_cancellable` calls, keyed by the
+    /// caller-supplied id, so [`SessionManager::cancel`] can reach one from a separate command
+    /// invocation (e.g. a dialog's close button) while the original call is still blocked inside
+    /// `with_session`.
+    cancellable: Mutex<HashMap<String, CancelToken>>,
+}
+
+/// An allow-list a command must match before [`SessionManager::check_command`] will let it
+/// through, for a kiosk/classroom "safe mode" deployment where arbitrary shell access is
+/// undesirable. Checked by the command layer before a channel is ever opened, so a blocked
+/// command never reaches a connection at all.
+#[derive(Clone)]
+pub struct CommandPolicy {
+    allow: Vec<Regex>,
+}
+
+impl CommandPolicy {
+    pub fn new(patterns: &[String]) -> Result<CommandPolicy, Error> {
+        let allow = patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::new(format!("Invalid command policy pattern: {e}")))?;
+        return Ok(CommandPolicy { allow });
+    }
+}
+
+/// One device's outcome from a multi-device `exec_on` fan-out, for a per-device results table.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceExecResult {
+    pub device_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+/// Snapshot of one device's connection pool, for an "active sessions" debug view.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolEntry {
+    pub device_name: String,
+    pub connections: u32,
+    pub idle_connections: u32,
 }
 
 pub struct Proc {
     pub(crate) device: Device,
     pub(crate) command: String,
+    pub(crate) pty: bool,
     pub(crate) callback: Mutex<Option<Box<dyn ProcCallback + Send>>>,
     pub(crate) ready: Arc<(Mutex<bool>, Condvar)>,
-    pub(crate) sender: Mutex<Option<Sender<Vec<u8>>>>,
+    pub(crate) sender: Mutex<Option<Sender<ProcMessage>>>,
     pub(crate) interrupted: Mutex<bool>,
+    pub(crate) seq: Mutex<u64>,
+    /// Whether to [`Proc::interrupt`] automatically once every `Arc<Proc>` handle is dropped,
+    /// so an abandoned process (e.g. the Tauri command that spawned it was cancelled before
+    /// cleaning up explicitly) doesn't keep running on the device forever. Off by default,
+    /// since plenty of callers intentionally `spawn` a fire-and-forget process and drop their
+    /// handle to it right away.
+    pub(crate) abort_on_drop: Mutex<bool>,
 }
 
+pub(crate) enum ProcMessage {
+    Data(Vec<u8>),
+    Eof,
+    Signal(String),
+}
+
+/// One chunk of `stdout`/`stderr` output from a [`Proc`]. `fd` is `0` for stdout, `1` for
+/// stderr; `seq` is a monotonic counter across both streams so a UI interleaving them (e.g. a
+/// live console view) can recover the order they actually arrived in even if IPC delivery
+/// reorders events.
 #[derive(Clone, Serialize)]
 pub struct ProcData {
     pub fd: u32,
+    pub seq: u64,
     pub data: Vec<u8>,
 }
 
@@ -41,5 +121,55 @@ pub enum ProcResult {
 }
 
 pub trait ProcCallback {
-    fn rx(&self, fd: u32, data: &[u8]);
+    fn rx(&self, fd: u32, seq: u64, data: &[u8]);
+}
+
+/// Wraps a per-line callback so it can be installed as a [`Proc`]'s [`ProcCallback`],
+/// reassembling lines that arrive split across chunk boundaries (e.g. `tail -F`'s output
+/// rarely lines up with SSH's read buffer size). Lines are tracked separately per `fd`.
+pub struct LineCallback<F: Fn(u32, &[u8]) + Send + Sync> {
+    on_line: F,
+    buffers: Mutex<HashMap<u32, Vec<u8>>>,
+}
+
+impl<F: Fn(u32, &[u8]) + Send + Sync> LineCallback<F> {
+    pub fn new(on_line: F) -> Self {
+        return LineCallback {
+            on_line,
+            buffers: Mutex::default(),
+        };
+    }
+
+    /// Emits whatever's left in each fd's buffer even though it never saw a trailing `\n`, so
+    /// the last line a process prints before closing its streams isn't silently dropped. Call
+    /// once the process has actually finished.
+    pub fn flush(&self) {
+        let mut buffers = self.buffers.lock().unwrap();
+        for (fd, buf) in buffers.iter_mut() {
+            if !buf.is_empty() {
+                (self.on_line)(*fd, buf);
+                buf.clear();
+            }
+        }
+    }
+}
+
+impl<F: Fn(u32, &[u8]) + Send + Sync> ProcCallback for LineCallback<F> {
+    fn rx(&self, fd: u32, _seq: u64, data: &[u8]) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let buf = buffers.entry(fd).or_default();
+        buf.extend_from_slice(data);
+        while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let without_nl = &line[..line.len() - 1];
+            let without_cr = without_nl.strip_suffix(b"\r").unwrap_or(without_nl);
+            (self.on_line)(fd, without_cr);
+        }
+    }
+}
+
+impl<T: ProcCallback + ?Sized> ProcCallback for Arc<T> {
+    fn rx(&self, fd: u32, seq: u64, data: &[u8]) {
+        (**self).rx(fd, seq, data);
+    }
 }
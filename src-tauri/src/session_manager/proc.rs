@@ -1,15 +1,46 @@
 use std::fmt::{Debug, Formatter};
 use std::io::Write;
 use std::sync::mpsc::channel;
+use std::sync::Arc;
 use std::time::Duration;
 
 use libssh_rs::Channel;
 
 use crate::conn_pool::ManagedDeviceConnection;
 use crate::error::Error;
-use crate::session_manager::{Proc, ProcResult, SessionManager};
+use crate::session_manager::{LineCallback, Proc, ProcMessage, ProcResult, SessionManager};
 
 impl Proc {
+    /// Runs this process to completion, invoking `on_line` with each complete line of output as
+    /// it arrives (`fd` is `0` for stdout, `1` for stderr), decoding invalid UTF-8 lossily and
+    /// flushing any trailing partial line once the process closes. Built on the same
+    /// [`LineCallback`] buffering a caller installing its own `ProcCallback` would otherwise
+    /// have to reimplement.
+    pub fn run_lines<F>(&self, sessions: &SessionManager, on_line: F) -> Result<ProcResult, Error>
+    where
+        F: Fn(u32, &str) + Send + Sync + 'static,
+    {
+        let line_callback = Arc::new(LineCallback::new(move |fd, line: &[u8]| {
+            on_line(fd, &String::from_utf8_lossy(line));
+        }));
+        *self.callback.lock().unwrap() = Some(Box::new(line_callback.clone()));
+        let result = self.wait_close(sessions);
+        self.callback.lock().unwrap().take();
+        line_callback.flush();
+        return result;
+    }
+
+    /// Builds `Error::Disconnected`, already tagged with this process's device and command, for
+    /// the handful of call sites here that only find out there's no live `sender` (i.e. the
+    /// worker thread already tore the channel down) rather than hitting the disconnect via a
+    /// propagated `?`.
+    fn disconnected(&self) -> Error {
+        return Error::Disconnected {
+            device: Some(self.device.name.clone()),
+            command: Some(crate::conn_pool::connection::redact_secrets(&self.command)),
+        };
+    }
+
     pub fn is_ready(&self) -> bool {
         let (lock, _cvar) = &*self.ready;
         return lock.lock().unwrap().clone();
@@ -35,31 +66,87 @@ impl Proc {
         *self.interrupted.lock().unwrap() = true;
     }
 
+    /// Sets whether this `Proc` should [`Proc::interrupt`] itself once every `Arc<Proc>` handle
+    /// to it is dropped, via its `Drop` impl. Off by default, since most callers (e.g.
+    /// fire-and-forget `spawn`s handed off to [`crate::spawn_manager::SpawnManager`]) are fine
+    /// leaving a process running unattended.
+    pub fn set_abort_on_drop(&self, abort: bool) {
+        *self.abort_on_drop.lock().unwrap() = abort;
+    }
+
+    /// Sends a POSIX signal (e.g. `"INT"`, `"TERM"`) to the remote process without closing the
+    /// channel, so it gets a chance to clean up. Use [`Proc::interrupt`] for the hard-kill path.
+    pub fn signal(&self, sig: &str) -> Result<(), Error> {
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            if let Ok(_) = sender.send(ProcMessage::Signal(String::from(sig))) {
+                return Ok(());
+            }
+            return Ok(());
+        }
+        return Err(self.disconnected());
+    }
+
     pub fn data(&self, fd: u32, data: &[u8]) -> Result<(), Error> {
         if let Some(cb) = self.callback.lock().unwrap().as_ref() {
-            cb.rx(fd, data);
+            let seq = {
+                let mut seq = self.seq.lock().unwrap();
+                *seq += 1;
+                *seq
+            };
+            cb.rx(fd, seq, data);
             return Ok(());
         }
-        return Err(Error::Disconnected);
+        return Err(self.disconnected());
     }
 
     pub fn write(&self, data: Vec<u8>) -> Result<(), Error> {
         if let Some(sender) = self.sender.lock().unwrap().as_ref() {
-            if let Ok(_) = sender.send(data) {
+            if let Ok(_) = sender.send(ProcMessage::Data(data)) {
+                return Ok(());
+            }
+            return Ok(());
+        }
+        return Err(self.disconnected());
+    }
+
+    /// Sends EOF on stdin so a process waiting on it (e.g. reading until EOF) can proceed,
+    /// without tearing down the whole channel the way `interrupt` does.
+    pub fn close_stdin(&self) -> Result<(), Error> {
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            if let Ok(_) = sender.send(ProcMessage::Eof) {
                 return Ok(());
             }
             return Ok(());
         }
-        return Err(Error::Disconnected);
+        return Err(self.disconnected());
     }
 
     pub fn wait_close(&self, sessions: &SessionManager) -> Result<ProcResult, Error> {
+        return self.wait_close_timeout(sessions, None);
+    }
+
+    /// Like [`Proc::wait_close`], but bounds the process's total wall-clock runtime: once
+    /// `deadline` passes, sends `TERM` and gives the process `kill_grace` to exit before
+    /// escalating to `KILL`, then returns [`Error::DeadlineExceeded`]. Distinct from
+    /// [`Proc::wait_close_timeout`]'s inactivity gap — a process that's still producing output
+    /// right up until the deadline is killed just the same.
+    pub fn wait_close_deadline(
+        &self,
+        sessions: &SessionManager,
+        deadline: std::time::Instant,
+        kill_grace: Duration,
+    ) -> Result<ProcResult, Error> {
         let session: ManagedDeviceConnection;
-        let (sender, receiver) = channel::<Vec<u8>>();
+        let (sender, receiver) = channel::<ProcMessage>();
         *self.sender.lock().unwrap() = Some(sender);
         let channel: Channel;
+        // Held for as long as `channel` is, same as every `exec`-family method in
+        // `connection.rs` — a long-lived `spawn`/`tail`/interactive-shell channel counts
+        // against the connection's channel cap exactly like a short-lived `exec` one does.
+        let _permit;
         loop {
             let conn = sessions.session(self.device.clone())?;
+            let permit = conn.acquire_channel_permit();
             let open = || {
                 let ch = conn.new_channel()?;
                 ch.open_session()?;
@@ -69,15 +156,151 @@ impl Proc {
                 Ok(ch) => {
                     session = conn;
                     channel = ch;
+                    _permit = permit;
                     break;
                 }
-                Err(Error::Disconnected) => continue,
+                Err(Error::Disconnected { .. }) => continue,
                 Err(e) => return Err(e),
             };
         }
+        if self.pty {
+            match channel.request_pty("xterm", 80, 24) {
+                Ok(_) => {}
+                Err(libssh_rs::Error::RequestDenied(s)) => {
+                    log::warn!("{self:?} failed to request pty {s:?}");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        session.record_command();
+        channel.request_exec(&self.command)?;
+        let mut buf = [0; 8192];
+        let mut term_sent_at: Option<std::time::Instant> = None;
+        let mut deadline_exceeded = false;
+        while !channel.is_closed() && !channel.is_eof() {
+            match term_sent_at {
+                None if std::time::Instant::now() >= deadline => {
+                    log::warn!("{self:?} exceeded its deadline, sending TERM");
+                    channel.request_send_signal("TERM")?;
+                    term_sent_at = Some(std::time::Instant::now());
+                }
+                Some(sent_at) if sent_at.elapsed() >= kill_grace => {
+                    log::warn!("{self:?} didn't exit within its grace period, sending KILL");
+                    channel.request_send_signal("KILL")?;
+                    channel.close()?;
+                    deadline_exceeded = true;
+                    break;
+                }
+                _ => {}
+            }
+            if let Ok(msg) = receiver.recv_timeout(Duration::from_micros(1)) {
+                match msg {
+                    ProcMessage::Data(data) => {
+                        session.record_written(data.len());
+                        channel.stdin().write_all(&data)?;
+                    }
+                    ProcMessage::Eof => channel.send_eof()?,
+                    ProcMessage::Signal(sig) => channel.request_send_signal(&sig)?,
+                }
+            }
+            let buf_size =
+                channel.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
+            if buf_size > 0 {
+                session.record_read(buf_size);
+                self.data(0, &buf[..buf_size])?;
+            }
+            let buf_size = channel.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
+            if buf_size > 0 {
+                session.record_read(buf_size);
+                self.data(1, &buf[..buf_size])?;
+            }
+        }
+        if deadline_exceeded {
+            return Err(Error::DeadlineExceeded);
+        }
+        let mut result = ProcResult::Closed;
+        if let Some(status) = channel.get_exit_status() {
+            log::debug!("{self:?} channel closed with status {status}");
+            result = ProcResult::Exit { status };
+        } else if let Some(signal) = channel.get_exit_signal() {
+            log::debug!("{self:?} channel closed with signal {signal:?}");
+            result = ProcResult::Signal {
+                signal: signal.signal_name,
+                core_dumped: signal.core_dumped,
+            };
+        } else {
+            log::debug!("{self:?} channel closed with unknown status");
+        }
+        session.mark_last_ok();
+        return Ok(result);
+    }
+
+    /// Like [`Proc::wait_close`], but aborts with [`Error::Timeout`] if `inactivity_timeout`
+    /// elapses between messages on either stream — a gap in output, not a cap on total runtime,
+    /// so a slow-but-alive process (e.g. a long `ares-install`) isn't killed just for taking a
+    /// while. `None` disables the timeout entirely, same as `wait_close`.
+    pub fn wait_close_timeout(
+        &self,
+        sessions: &SessionManager,
+        inactivity_timeout: Option<Duration>,
+    ) -> Result<ProcResult, Error> {
+        let started = std::time::Instant::now();
+        let result = self.wait_close_timeout_inner(sessions, inactivity_timeout);
+        let exit_status = match &result {
+            Ok(ProcResult::Exit { status }) => Some(*status),
+            _ => None,
+        };
+        sessions.record_audit(&self.device.name, &self.command, exit_status, started.elapsed());
+        return result;
+    }
+
+    fn wait_close_timeout_inner(
+        &self,
+        sessions: &SessionManager,
+        inactivity_timeout: Option<Duration>,
+    ) -> Result<ProcResult, Error> {
+        let session: ManagedDeviceConnection;
+        let (sender, receiver) = channel::<ProcMessage>();
+        *self.sender.lock().unwrap() = Some(sender);
+        let channel: Channel;
+        // Held for as long as `channel` is, same as every `exec`-family method in
+        // `connection.rs` — a long-lived `spawn`/`tail`/interactive-shell channel counts
+        // against the connection's channel cap exactly like a short-lived `exec` one does.
+        let _permit;
+        loop {
+            let conn = sessions.session(self.device.clone())?;
+            let permit = conn.acquire_channel_permit();
+            let open = || {
+                let ch = conn.new_channel()?;
+                ch.open_session()?;
+                Ok(ch)
+            };
+            match open() {
+                Ok(ch) => {
+                    session = conn;
+                    channel = ch;
+                    _permit = permit;
+                    break;
+                }
+                Err(Error::Disconnected { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+        }
+        if self.pty {
+            match channel.request_pty("xterm", 80, 24) {
+                Ok(_) => {}
+                Err(libssh_rs::Error::RequestDenied(s)) => {
+                    log::warn!("{self:?} failed to request pty {s:?}");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        session.record_command();
         channel.request_exec(&self.command)?;
         let mut buf = [0; 8192];
         let mut interrupted = false;
+        let mut timed_out = false;
+        let mut last_activity = std::time::Instant::now();
         while !channel.is_closed() && !channel.is_eof() {
             if self.interrupted.lock().unwrap().eq(&true) {
                 channel.send_eof()?;
@@ -86,19 +309,48 @@ impl Proc {
                 channel.close()?;
                 interrupted = true;
                 break;
-            } else if let Ok(msg) = receiver.recv_timeout(Duration::from_micros(1)) {
-                channel.stdin().write_all(&msg)?;
+            } else if let Some(inactivity_timeout) = inactivity_timeout {
+                if last_activity.elapsed() > inactivity_timeout {
+                    log::warn!("{self:?} timed out after {inactivity_timeout:?} of inactivity");
+                    channel.send_eof()?;
+                    channel.request_send_signal("TERM")?;
+                    channel.close()?;
+                    timed_out = true;
+                    break;
+                }
+            }
+            if let Ok(msg) = receiver.recv_timeout(Duration::from_micros(1)) {
+                match msg {
+                    // `libssh_rs::Channel`'s `Write` impl blocks at the libssh C level until
+                    // the whole buffer is accepted (it has no exposed partial-write/backpressure
+                    // API the way an async channel would), and `write_all` itself already loops
+                    // until every byte is written or an error occurs — so a large `write()` here
+                    // can't silently truncate, even without chunking it ourselves.
+                    ProcMessage::Data(data) => {
+                        session.record_written(data.len());
+                        channel.stdin().write_all(&data)?;
+                    }
+                    ProcMessage::Eof => channel.send_eof()?,
+                    ProcMessage::Signal(sig) => channel.request_send_signal(&sig)?,
+                }
             }
             let buf_size =
                 channel.read_timeout(&mut buf, false, Some(Duration::from_millis(10)))?;
             if buf_size > 0 {
+                session.record_read(buf_size);
                 self.data(0, &buf[..buf_size])?;
+                last_activity = std::time::Instant::now();
             }
             let buf_size = channel.read_timeout(&mut buf, true, Some(Duration::from_millis(10)))?;
             if buf_size > 0 {
+                session.record_read(buf_size);
                 self.data(1, &buf[..buf_size])?;
+                last_activity = std::time::Instant::now();
             }
         }
+        if timed_out {
+            return Err(Error::Timeout);
+        }
         let mut result = ProcResult::Closed;
         if interrupted {
             log::debug!("{self:?} channel interrupted by client");
@@ -123,6 +375,21 @@ impl Proc {
     }
 }
 
+impl Drop for Proc {
+    /// Best-effort cleanup for a `Proc` that's being dropped with `abort_on_drop` set: flags it
+    /// interrupted so its `wait_close*` loop (if still running in its `spawn_blocking` worker
+    /// thread) sends `TERM` and closes the channel on its next iteration, the same hard-kill
+    /// path [`crate::spawn_manager::SpawnManager::clear`] already uses on app exit. This can't
+    /// reach into and cancel a `spawn_blocking` thread directly — Tokio doesn't expose that — so
+    /// it only guards against every handle being dropped without the worker noticing, not a
+    /// mid-flight future cancellation.
+    fn drop(&mut self) {
+        if *self.abort_on_drop.lock().unwrap() {
+            self.interrupt();
+        }
+    }
+}
+
 impl Debug for Proc {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
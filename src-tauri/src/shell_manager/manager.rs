@@ -36,6 +36,15 @@ impl ShellManager {
         return Ok(());
     }
 
+    /// Closes every open shell, on app exit — otherwise each one's worker thread would keep
+    /// its SSH channel open past the point anything is left listening to it.
+    pub fn close_all(&self) {
+        let tokens: Vec<ShellToken> = self.shells.lock().unwrap().keys().cloned().collect();
+        for token in tokens {
+            self.close(&token).unwrap_or(());
+        }
+    }
+
     pub fn list(&self) -> Vec<ShellInfo> {
         let mut list: Vec<ShellInfo> = self
             .shells
@@ -132,7 +132,10 @@ impl Shell {
                 return Ok(());
             }
         }
-        return Err(Error::Disconnected);
+        return Err(Error::Disconnected {
+            device: Some(self.device.name.clone()),
+            command: None,
+        });
     }
 
     fn worker(&self) -> Result<i32, Error> {